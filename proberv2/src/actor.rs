@@ -1,64 +1,229 @@
 use std::{
-    collections::HashMap,
-    net::{IpAddr, Ipv4Addr},
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    sync::Arc,
     time::Duration,
 };
 use tokio::{
-    sync::broadcast::{self, error::RecvError},
-    time,
+    sync::broadcast::{
+        self,
+        error::{RecvError, TryRecvError},
+    },
+    time::{self, Instant},
 };
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    config::Config, cpu_usage_probe::probe_cpu_usage, endpoints_watch::watch_endpoints,
-    latency_probe::probe_latency, node_watch::watch_nodes, update_nftables::update_nftables,
+    config::Config,
+    cpu_usage_probe::probe_cpu_usage,
+    endpoints_watch::watch_endpoints,
+    icmp_probe::probe_icmp,
+    latency_probe::probe_latency,
+    memory_probe::probe_memory,
+    metrics,
+    network_probe::probe_network,
+    nftables_backend::NftablesBackend,
+    node_watch::watch_nodes,
+    pod_cpu_probe::probe_pod_cpu,
+    teardown_nftables::teardown_nftables,
+    update_nftables::{RulesetUpdate, ServiceRuleset, update_nftables},
 };
 
+/// Kapasitas channel event. Setiap probe mengirim satu event per node atau per endpoint
+/// Service setiap tick sehingga kapasitas harus menampung seluruhnya diantara dua drain
+pub const EVENT_CAPACITY: usize = 4096;
+
 pub struct Actor {
     pub config: Config,
+    pub backend: Arc<dyn NftablesBackend>,
     pub datapoint_by_nodename: HashMap<String, Option<ScorePair>>,
-    pub service_by_nodeport: HashMap<i32, Service>,
+    // Service Key -> Node Name -> (EWMA latency, waktu pembaruan)
+    pub latency_by_service: HashMap<String, HashMap<String, (f64, Instant)>>,
+    // Service Key -> IP endpoint -> datapoint setiap pod
+    pub endpoint_datapoint_by_service: HashMap<String, HashMap<IpAddr, EndpointDatapoint>>,
+    pub service_by_key: HashMap<String, Service>,
+    // Service Key -> node yang sedang di-eject karena gagal merespon probe latency
+    pub ejected_by_service: HashMap<String, HashSet<String>>,
+    // Service Key -> port Service yang aturan nftables-nya sedang terpasang
+    pub steered_ports: HashMap<String, Vec<ServicePort>>,
+    // Service Key -> state yang terakhir diterapkan, penerapan ulang state yang sama dilewati
+    pub applied_by_service: HashMap<String, ServiceRuleset>,
 }
 
 #[derive(Clone)]
 pub enum Event {
     ServiceChanged(Service),
+    ServiceRemoved(Service),
     // String == Node Name, probably should separate type?
     EwmaCalculated(String, EwmaDatapoint),
+    // (Service Key, Node Name), datapoint yang hanya berlaku untuk Service tertentu
+    ServiceEwmaCalculated(String, String, EwmaDatapoint),
+    // (Service Key, IP endpoint), datapoint sebuah pod untuk membagi porsi node kepada pod-podnya
+    EndpointEwmaCalculated(String, IpAddr, EwmaDatapoint),
+    // (Service Key, Node Name), node dikeluarkan dari atau diterima kembali pada pembagian traffic Service
+    NodeEjected(String, String),
+    NodeReadmitted(String, String),
     NodeJoined(WorkerNode),
+    NodeUpdated(WorkerNode),
+    NodeLeft(WorkerNode),
 }
 
 #[derive(Eq, Hash, PartialEq, Clone, Debug)]
 pub struct WorkerNode {
     pub name: String,
     pub ip: IpAddr,
+    // alamat Hostname pada status Node, nama Node ketika tidak tersedia
+    pub hostname: String,
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct ScorePair {
     pub latency: f64,
     pub cpu: f64,
+    // RTT jaringan (ICMP) dalam milidetik, 0 ketika belum terukur
+    pub rtt: f64,
+    // tekanan memori (0 hingga 1), 0 ketika belum terukur
+    pub memory: f64,
+    // saturasi jaringan (0 hingga 1), 0 ketika belum terukur
+    pub network: f64,
+    // saturasi CPU pod Service pada node (0 hingga 1), 0 ketika belum terukur
+    pub pod_cpu: f64,
+    // waktu pembaruan tertua sinyal CPU dan latency, `None` ketika tidak diketahui
+    pub updated_at: Option<Instant>,
+}
+
+/// Datapoint sebuah pod Service, sinyal yang belum terukur memakai datapoint node
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EndpointDatapoint {
+    pub latency: Option<f64>,
+    // pemakaian CPU terhadap limit atau rasio throttling CFS (0 hingga 1)
+    pub pod_cpu: Option<f64>,
 }
 
 #[derive(Clone)]
 pub enum EwmaDatapoint {
     Latency(f64),
     Cpu(f64),
+    Rtt(f64),
+    Memory(f64),
+    Network(f64),
+    PodCpu(f64),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Service {
+    pub namespace: String,
     pub name: String,
+    pub ports: Vec<ServicePort>,
+    pub endpoints_by_nodename: HashMap<String, Vec<Endpoint>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServicePort {
+    pub protocol: Protocol,
     pub nodeport: i32,
     pub targetport: i32,
-    pub endpoints_by_nodename: HashMap<String, Vec<Ipv4Addr>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+    Sctp,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Endpoint {
+    pub ip: IpAddr,
+    // nama Pod pada namespace Service, diambil dari targetRef EndpointSlice
+    pub pod: Option<String>,
+    // zona topologi tempat pod berjalan, diambil dari EndpointSlice
+    pub zone: Option<String>,
+}
+
+/// Membaca event yang sudah tersedia pada channel tanpa menunggu. Receiver yang tertinggal
+/// melewati event yang telah ditimpa lalu melanjutkan pembacaan event berikutnya
+pub fn try_next_event(rx: &mut broadcast::Receiver<Event>) -> Option<Event> {
+    loop {
+        match rx.try_recv() {
+            Ok(event) => return Some(event),
+            Err(TryRecvError::Lagged(skipped)) => {
+                warn!("actor: receiver lagged behind, {skipped} events were dropped");
+            }
+            Err(_) => return None,
+        }
+    }
+}
+
+impl Service {
+    /// Identitas unik Service di dalam cluster dengan format <namespace>/<nama>
+    pub fn key(&self) -> String {
+        format!("{}/{}", self.namespace, self.name)
+    }
+
+    /// Port yang digunakan untuk mengukur latency Service, probe hanya dapat
+    /// dilakukan melalui port TCP
+    pub fn probe_port(&self) -> Option<&ServicePort> {
+        self.ports
+            .iter()
+            .find(|port| port.protocol == Protocol::Tcp)
+    }
+
+    /// Apakah IP tersebut merupakan salah satu endpoint Service
+    pub fn has_endpoint(&self, ip: IpAddr) -> bool {
+        self.endpoints_by_nodename
+            .values()
+            .flatten()
+            .any(|endpoint| endpoint.ip == ip)
+    }
+}
+
+impl ServicePort {
+    /// Dua port dianggap sama ketika menempati NodePort dan protokol yang sama
+    pub fn same_nodeport(&self, other: &ServicePort) -> bool {
+        self.protocol == other.protocol && self.nodeport == other.nodeport
+    }
+}
+
+impl Protocol {
+    pub fn parse(protocol: &str) -> Option<Self> {
+        match protocol {
+            "TCP" => Some(Protocol::Tcp),
+            "UDP" => Some(Protocol::Udp),
+            "SCTP" => Some(Protocol::Sctp),
+            _ => None,
+        }
+    }
+
+    /// Nama protokol sesuai penulisan nftables (inet_proto)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+            Protocol::Sctp => "sctp",
+        }
+    }
 }
 
 impl Actor {
+    pub fn new(config: Config, backend: Arc<dyn NftablesBackend>) -> Self {
+        Self {
+            config,
+            backend,
+            datapoint_by_nodename: HashMap::new(),
+            latency_by_service: HashMap::new(),
+            endpoint_datapoint_by_service: HashMap::new(),
+            service_by_key: HashMap::new(),
+            ejected_by_service: HashMap::new(),
+            steered_ports: HashMap::new(),
+            applied_by_service: HashMap::new(),
+        }
+    }
+
     pub async fn dispatch(&mut self, token: CancellationToken) {
         info!("actor: starting processes");
-        let (tx, mut rx) = broadcast::channel(32);
+        let (tx, mut rx) = broadcast::channel(EVENT_CAPACITY);
 
         tokio::spawn({
             let token = token.clone();
@@ -72,6 +237,22 @@ impl Actor {
             let token = token.clone();
             probe_cpu_usage(self.config.clone(), tx.clone(), token)
         });
+        tokio::spawn({
+            let token = token.clone();
+            probe_icmp(self.config.clone(), tx.clone(), token)
+        });
+        tokio::spawn({
+            let token = token.clone();
+            probe_memory(self.config.clone(), tx.clone(), token)
+        });
+        tokio::spawn({
+            let token = token.clone();
+            probe_network(self.config.clone(), tx.clone(), token)
+        });
+        tokio::spawn({
+            let token = token.clone();
+            probe_pod_cpu(self.config.clone(), tx.clone(), token)
+        });
         tokio::spawn({
             let token = token.clone();
             watch_endpoints(self.config.clone(), tx.clone(), token)
@@ -86,63 +267,301 @@ impl Actor {
                     break 'main
                 },
                 _ = ticker.tick() => {
-                    for service in self.service_by_nodeport.values() {
-                        if let Err(e) = update_nftables(
-                            self.config.clone(),
-                            service.clone(),
-                            self.datapoint_by_nodename.clone(),
-                        )
-                        .await
-                        {
-                            error!("actor: reacting to service endpoints update failed: {e}");
-                        };
-                    }
+                    self.tick().await;
                     continue 'main
                 }
             };
             let event = match event {
                 Ok(event) => event,
                 Err(RecvError::Closed) => break 'main,
-                _ => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("actor: dispatch lagged behind, {skipped} events were dropped");
+                    continue;
+                }
             };
 
-            match event {
-                Event::ServiceChanged(service) => {
-                    self.service_by_nodeport
-                        .insert(service.nodeport, service.clone());
-                    if let Err(e) = update_nftables(
-                        self.config.clone(),
-                        service,
-                        self.datapoint_by_nodename.clone(),
-                    )
-                    .await
-                    {
-                        error!("actor: reacting to service endpoints update failed: {e}");
-                    };
+            self.handle(event).await;
+        }
+    }
+
+    /// Menerapkan ulang bobot terbaru pada seluruh Service yang dikendalikan
+    pub async fn tick(&mut self) {
+        let services: Vec<Service> = self.service_by_key.values().cloned().collect();
+        for service in services {
+            self.reconcile(service).await;
+        }
+    }
+
+    /// Memperbarui state actor berdasarkan sebuah event dari proses lain
+    pub async fn handle(&mut self, event: Event) {
+        match event {
+            Event::ServiceChanged(service) => {
+                // latency dari node yang tidak lagi menjalankan endpoint Service sudah usang
+                if let Some(latency_by_nodename) = self.latency_by_service.get_mut(&service.key()) {
+                    latency_by_nodename
+                        .retain(|nodename, _| service.endpoints_by_nodename.contains_key(nodename));
+                }
+                if let Some(datapoint_by_ip) =
+                    self.endpoint_datapoint_by_service.get_mut(&service.key())
+                {
+                    datapoint_by_ip.retain(|ip, _| service.has_endpoint(*ip));
                 }
-                Event::EwmaCalculated(worker, dp) => {
-                    let Some(score) = self.datapoint_by_nodename.get_mut(&worker) else {
-                        warn!("actor: ghost node {} got ewma calculation", worker);
-                        continue;
-                    };
-                    let score = score.get_or_insert_with(ScorePair::default);
-
-                    match dp {
-                        EwmaDatapoint::Latency(v) => score.latency = v,
-                        EwmaDatapoint::Cpu(v) => score.cpu = v,
+                if let Some(ejected) = self.ejected_by_service.get_mut(&service.key()) {
+                    ejected.retain(|nodename| service.endpoints_by_nodename.contains_key(nodename));
+                }
+                self.service_by_key.insert(service.key(), service.clone());
+                self.reconcile(service).await;
+            }
+            Event::ServiceRemoved(service) => {
+                info!("actor: releasing removed service {}", service.key());
+                self.service_by_key.remove(&service.key());
+                self.latency_by_service.remove(&service.key());
+                self.endpoint_datapoint_by_service.remove(&service.key());
+                self.ejected_by_service.remove(&service.key());
+                self.release(&service, |_| true).await;
+                metrics::remove_service(&service.key(), self.datapoint_by_nodename.keys());
+            }
+            Event::EwmaCalculated(worker, dp) => {
+                let Some(score) = self.datapoint_by_nodename.get_mut(&worker) else {
+                    warn!("actor: ghost node {} got ewma calculation", worker);
+                    return;
+                };
+                let score = score.get_or_insert_with(ScorePair::default);
+
+                match dp {
+                    EwmaDatapoint::Latency(v) => score.latency = v,
+                    EwmaDatapoint::Cpu(v) => {
+                        score.cpu = v;
+                        score.updated_at = Some(Instant::now());
                     }
+                    EwmaDatapoint::Rtt(v) => score.rtt = v,
+                    EwmaDatapoint::Memory(v) => score.memory = v,
+                    EwmaDatapoint::Network(v) => score.network = v,
+                    // datapoint pod dikirim melalui EndpointEwmaCalculated
+                    EwmaDatapoint::PodCpu(_) => {}
+                }
 
-                    info!(
-                        "actor: updated node {} with latency {} cpu {}",
-                        worker, score.latency, score.cpu
-                    );
+                info!(
+                    "actor: updated node {} with cpu {} rtt {} memory {} network {}",
+                    worker, score.cpu, score.rtt, score.memory, score.network
+                );
+            }
+            Event::ServiceEwmaCalculated(service, worker, dp) => {
+                if !self.datapoint_by_nodename.contains_key(&worker) {
+                    warn!("actor: ghost node {} got ewma calculation", worker);
+                    return;
+                }
+                let EwmaDatapoint::Latency(v) = dp else {
+                    return;
+                };
+                info!(
+                    "actor: updated service {} node {} with latency {}",
+                    service, worker, v
+                );
+                self.latency_by_service
+                    .entry(service)
+                    .or_default()
+                    .insert(worker, (v, Instant::now()));
+            }
+            Event::EndpointEwmaCalculated(service, ip, dp) => {
+                let datapoint = self
+                    .endpoint_datapoint_by_service
+                    .entry(service.clone())
+                    .or_default()
+                    .entry(ip)
+                    .or_default();
+                match dp {
+                    EwmaDatapoint::Latency(v) => datapoint.latency = Some(v),
+                    EwmaDatapoint::PodCpu(v) => datapoint.pod_cpu = Some(v),
+                    _ => return,
+                }
+                debug!("actor: updated service {service} endpoint {ip} with {datapoint:?}");
+            }
+            Event::NodeEjected(service, worker) => {
+                debug!("actor: excluding ejected node {worker} from service {service}");
+                self.ejected_by_service
+                    .entry(service.clone())
+                    .or_default()
+                    .insert(worker);
+                // traffic segera dialihkan dari node yang tidak merespon tanpa menunggu tick
+                if let Some(service) = self.service_by_key.get(&service).cloned() {
+                    self.reconcile(service).await;
+                }
+            }
+            Event::NodeReadmitted(service, worker) => {
+                debug!("actor: including readmitted node {worker} in service {service}");
+                if let Some(ejected) = self.ejected_by_service.get_mut(&service) {
+                    ejected.remove(&worker);
+                }
+            }
+            Event::NodeJoined(worker) => {
+                self.datapoint_by_nodename
+                    .entry(worker.name)
+                    .or_insert(None);
+            }
+            // alamat yang berubah berarti instance node telah diganti, sinyal dan ejection
+            // instance lama direset sehingga node tidak menerima traffic hingga terukur kembali
+            Event::NodeUpdated(worker) => {
+                info!("actor: resetting datapoints of updated node {}", worker.name);
+                self.datapoint_by_nodename.insert(worker.name.clone(), None);
+                for latency_by_nodename in self.latency_by_service.values_mut() {
+                    latency_by_nodename.remove(&worker.name);
+                }
+                for ejected in self.ejected_by_service.values_mut() {
+                    ejected.remove(&worker.name);
+                }
+            }
+            Event::NodeLeft(worker) => {
+                info!("actor: evicting departed node {}", worker.name);
+                self.datapoint_by_nodename.remove(&worker.name);
+                for latency_by_nodename in self.latency_by_service.values_mut() {
+                    latency_by_nodename.remove(&worker.name);
                 }
-                Event::NodeJoined(worker) => {
-                    self.datapoint_by_nodename
-                        .entry(worker.name)
-                        .or_insert(None);
+                for ejected in self.ejected_by_service.values_mut() {
+                    ejected.remove(&worker.name);
                 }
+                metrics::remove_node(&worker.name, self.service_by_key.keys());
             }
         }
     }
+
+    /// Menerapkan aturan pembagian traffic Service yang tersebar pada minimal 2 node,
+    /// atau mengembalikan Service kepada kube-proxy ketika endpoint menyusut dibawah batas tersebut
+    /// maupun ketika kebijakan fallback Service memintanya
+    async fn reconcile(&mut self, service: Service) {
+        if service.endpoints_by_nodename.len() < 2 {
+            if self.release(&service, |_| true).await {
+                metrics::remove_service_steering(&service.key(), self.datapoint_by_nodename.keys());
+            }
+            return;
+        }
+
+        // port yang tidak lagi dimiliki Service (dihapus atau NodePort berubah) dilepas
+        // terlebih dahulu sebelum aturan port yang tersisa diterapkan
+        self.release(&service, |port| {
+            !service
+                .ports
+                .iter()
+                .any(|current| current.same_nodeport(port))
+        })
+        .await;
+
+        let datapoints = self.datapoints_for(&service);
+        let key = service.key();
+        let ports = service.ports.clone();
+        let original = service.clone();
+        match update_nftables(
+            self.config.clone(),
+            self.backend.as_ref(),
+            service,
+            datapoints,
+            self.endpoint_datapoint_by_service
+                .get(&key)
+                .unwrap_or(&HashMap::new()),
+            self.applied_by_service.get(&key),
+        )
+        .await
+        {
+            Ok(RulesetUpdate::Applied(applied)) => {
+                self.steered_ports.insert(key.clone(), ports);
+                self.applied_by_service.insert(key, applied);
+            }
+            Ok(RulesetUpdate::Skipped) => {}
+            Ok(RulesetUpdate::Released) => {
+                if self.release(&original, |_| true).await {
+                    metrics::remove_service_steering(&key, self.datapoint_by_nodename.keys());
+                }
+            }
+            Err(e) => {
+                // transaksi yang gagal tidak mengubah ruleset sama sekali, namun state terpasang
+                // tidak lagi dapat dipastikan sehingga tick berikutnya selalu menerapkan ulang
+                self.applied_by_service.remove(&key);
+                error!("actor: reacting to service endpoints update failed: {e}");
+            }
+        }
+    }
+
+    /// Menghapus aturan nftables dari port Service terpasang yang memenuhi filter,
+    /// mengembalikan `true` ketika seluruh port Service telah dilepas
+    async fn release(&mut self, service: &Service, filter: impl Fn(&ServicePort) -> bool) -> bool {
+        let key = service.key();
+        let Some(steered) = self.steered_ports.remove(&key) else {
+            return false;
+        };
+        if steered.iter().any(&filter) {
+            self.applied_by_service.remove(&key);
+        }
+
+        let mut remaining = Vec::new();
+        for port in steered {
+            if !filter(&port) {
+                remaining.push(port);
+                continue;
+            }
+            if let Err(e) = teardown_nftables(
+                self.config.clone(),
+                self.backend.as_ref(),
+                key.clone(),
+                port.clone(),
+            )
+            .await
+            {
+                error!(
+                    "actor: tearing down service {key} port {} failed: {e}",
+                    port.nodeport
+                );
+                remaining.push(port);
+            }
+        }
+
+        if remaining.is_empty() {
+            return true;
+        }
+        self.steered_ports.insert(key, remaining);
+        false
+    }
+
+    /// Menggabungkan datapoint tingkat node (CPU) dengan latency yang diukur khusus untuk
+    /// Service, node hanya memiliki skor ketika kedua sinyal telah tersedia dan tidak di-eject.
+    /// Saturasi CPU pod Service pada node merupakan rata-rata pod yang telah terukur
+    fn datapoints_for(&self, service: &Service) -> HashMap<String, Option<ScorePair>> {
+        let latency_by_nodename = self.latency_by_service.get(&service.key());
+        let ejected = self.ejected_by_service.get(&service.key());
+        let datapoint_by_ip = self.endpoint_datapoint_by_service.get(&service.key());
+        let pod_cpu = |nodename: &str| {
+            let pod_cpus: Vec<f64> = service
+                .endpoints_by_nodename
+                .get(nodename)
+                .into_iter()
+                .flatten()
+                .filter_map(|endpoint| datapoint_by_ip?.get(&endpoint.ip)?.pod_cpu)
+                .collect();
+            if pod_cpus.is_empty() {
+                0.0
+            } else {
+                pod_cpus.iter().sum::<f64>() / pod_cpus.len() as f64
+            }
+        };
+        self.datapoint_by_nodename
+            .iter()
+            .map(|(nodename, datapoint)| {
+                let latency = latency_by_nodename
+                    .and_then(|latencies| latencies.get(nodename))
+                    .filter(|_| !ejected.is_some_and(|ejected| ejected.contains(nodename)));
+                // sinyal lain dapat tiba lebih dulu, node baru terukur setelah CPU tersedia
+                let datapoint = match (datapoint, latency) {
+                    (Some(datapoint), Some((latency, latency_at))) => {
+                        datapoint.updated_at.map(|cpu_at| ScorePair {
+                            latency: *latency,
+                            pod_cpu: pod_cpu(nodename),
+                            updated_at: Some(cpu_at.min(*latency_at)),
+                            ..datapoint.clone()
+                        })
+                    }
+                    _ => None,
+                };
+                (nodename.clone(), datapoint)
+            })
+            .collect()
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, HashMap};
+
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -10,6 +12,18 @@ pub struct Config {
     pub probe: ProbeConfig,
     pub alpha: AlphaConfig,
     #[serde(default)]
+    pub scoring: ScoringConfig,
+    #[serde(default)]
+    pub dampening: DampeningConfig,
+    #[serde(default)]
+    pub outlier: OutlierConfig,
+    /// Jendela kelayakan node untuk Service yang tidak memiliki jendela khusus
+    #[serde(default)]
+    pub eligibility: EligibilityConfig,
+    /// Service Key (<namespace>/<nama>) -> jendela kelayakan khusus Service tersebut
+    #[serde(default)]
+    pub service_eligibility: HashMap<String, EligibilityConfig>,
+    #[serde(default)]
     pub metrics: MetricsConfig,
 }
 
@@ -17,6 +31,46 @@ pub struct Config {
 #[serde(rename_all = "camelCase")]
 pub struct PrometheusConfig {
     pub url: String,
+    /// Query PromQL pemakaian CPU (0 hingga 1) seluruh node yang dievaluasi sekali setiap tick,
+    /// `{{targets}}` diganti dengan regex alternatif nilai join seluruh node yang diketahui
+    #[serde(default = "default_cpu_query")]
+    pub cpu_query: String,
+    /// Query PromQL tekanan memori (0 hingga 1) seluruh node, misalnya porsi memori yang tidak
+    /// tersedia atau PSI `rate(node_pressure_memory_waiting_seconds_total[1m])`
+    #[serde(default = "default_memory_query")]
+    pub memory_query: String,
+    /// Query PromQL utilisasi (0 hingga 1) interface tersibuk setiap node terhadap kecepatan link
+    #[serde(default = "default_network_utilization_query")]
+    pub network_utilization_query: String,
+    /// Query PromQL rasio paket drop dan error (0 hingga 1) setiap node
+    #[serde(default = "default_network_error_query")]
+    pub network_error_query: String,
+    /// Query PromQL pemakaian CPU setiap pod terhadap limit-nya (0 hingga 1), dijoin melalui
+    /// label `namespace` dan `pod` milik cAdvisor. `{{pods}}` diganti dengan regex nama
+    /// seluruh pod endpoint Service
+    #[serde(default = "default_pod_cpu_usage_query")]
+    pub pod_cpu_usage_query: String,
+    /// Query PromQL rasio periode CFS yang ter-throttle (0 hingga 1) setiap pod
+    #[serde(default = "default_pod_cpu_throttling_query")]
+    pub pod_cpu_throttling_query: String,
+    /// Label hasil query yang digunakan untuk mencocokkan series dengan node
+    #[serde(default = "default_join_label")]
+    pub join_label: String,
+    /// Nilai node yang dicocokkan dengan `joinLabel`, port pada nilai label diabaikan
+    #[serde(default)]
+    pub join_by: PrometheusJoin,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PrometheusJoin {
+    /// InternalIP node, seperti label `instance` milik node_exporter
+    #[default]
+    Ip,
+    /// Alamat Hostname pada status Node
+    Hostname,
+    /// Nama objek Node, seperti label `node` milik kube-state-metrics
+    NodeName,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -26,6 +80,8 @@ pub struct NftablesConfig {
     pub chain_prerouting: String,
     pub chain_services: String,
     pub set_allowed_node_ips: String,
+    #[serde(default = "default_set_allowed_node_ipv6s")]
+    pub set_allowed_node_ipv6s: String,
     pub map_service_chain_by_nodeport: String,
     pub prefix_service_endpoint: String,
     pub probability_cap: u32,
@@ -34,11 +90,17 @@ pub struct NftablesConfig {
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct KubernetesConfig {
-    pub namespace: String,
+    /// Namespace yang dipantau, kosong berarti seluruh namespace
+    #[serde(default)]
+    pub namespaces: Vec<String>,
     #[serde(skip)]
     pub node_name: String,
-    pub service: String,
-    pub target_port: u32,
+    /// Label selector untuk memilih Service yang dikendalikan
+    #[serde(default)]
+    pub label_selector: Option<String>,
+    /// Anotasi yang harus bernilai "true" pada Service, `null` untuk hanya memakai label selector
+    #[serde(default = "default_service_annotation")]
+    pub annotation: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -46,7 +108,215 @@ pub struct KubernetesConfig {
 pub struct ProbeConfig {
     pub latency_interval: u64,
     pub cpu_interval: u64,
+    /// Sumber pemakaian CPU node
+    #[serde(default)]
+    pub cpu_source: CpuSourceConfig,
+    /// Interval pembacaan tekanan memori node dalam detik
+    #[serde(default = "default_memory_interval")]
+    pub memory_interval: u64,
+    /// Sumber tekanan memori node
+    #[serde(default)]
+    pub memory_source: MemorySourceConfig,
+    /// Interval pembacaan saturasi jaringan node dalam detik
+    #[serde(default = "default_network_interval")]
+    pub network_interval: u64,
+    /// Sumber saturasi jaringan node
+    #[serde(default)]
+    pub network_source: NetworkSourceConfig,
+    /// Interval pembacaan CPU pod Service dalam detik
+    #[serde(default = "default_pod_cpu_interval")]
+    pub pod_cpu_interval: u64,
+    /// Sumber pemakaian CPU pod Service
+    #[serde(default)]
+    pub pod_cpu_source: PodCpuSourceConfig,
     pub nft_update_interval: u64,
+    /// Interval ICMP echo menuju setiap node dalam detik
+    #[serde(default = "default_icmp_interval")]
+    pub icmp_interval: u64,
+    /// Batas waktu menunggu ICMP echo reply dalam milidetik
+    #[serde(default = "default_icmp_timeout_ms")]
+    pub icmp_timeout_ms: u64,
+    /// Jumlah sampel latency setiap node dalam satu tick, tersebar bergantian pada endpoint node
+    /// tersebut dengan minimal satu sampel setiap endpoint
+    #[serde(default = "default_latency_samples")]
+    pub latency_samples: usize,
+    /// Statistik sampel yang digunakan sebagai latency node pada tick tersebut
+    #[serde(default)]
+    pub latency_statistic: LatencyStatistic,
+    /// Porsi sampel (0 hingga 0.5) yang dibuang dari setiap ujung pada statistik `trimmedMean`
+    #[serde(default = "default_trim_fraction")]
+    pub trim_fraction: f64,
+    /// Latency dalam milidetik yang dicatat ketika probe seluruh endpoint node gagal
+    #[serde(default = "default_failure_penalty_ms")]
+    pub failure_penalty_ms: f64,
+    #[serde(default)]
+    pub http: HttpProbeConfig,
+    /// Pemeriksaan untuk Service yang tidak memiliki pemeriksaan khusus
+    #[serde(default)]
+    pub default_check: ProbeCheckConfig,
+    /// Service Key (<namespace>/<nama>) -> pemeriksaan khusus Service tersebut
+    #[serde(default)]
+    pub service_checks: HashMap<String, ProbeCheckConfig>,
+}
+
+/// Pemeriksaan probe latency sebuah Service, field HTTP hanya berlaku pada mode `http`
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeCheckConfig {
+    #[serde(default)]
+    pub mode: ProbeMode,
+    #[serde(flatten)]
+    pub http: HttpCheckConfig,
+    /// Nama service pada `HealthCheckRequest` mode `grpc`, kosong berarti seluruh server
+    #[serde(default)]
+    pub grpc_service: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProbeMode {
+    /// Request HTTP sesuai `HttpCheckConfig`
+    #[default]
+    Http,
+    /// Three-way handshake TCP pada target port, dibatasi `http.connectTimeoutMs`
+    Tcp,
+    /// RPC `grpc.health.v1.Health/Check`, dibatasi batas waktu yang sama dengan mode `http`
+    Grpc,
+}
+
+/// Request yang dikirim probe latency beserta syarat response yang dianggap berhasil
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpCheckConfig {
+    #[serde(default = "default_check_path")]
+    pub path: String,
+    #[serde(default = "default_check_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Status response yang diterima, kosong berarti seluruh status 2xx
+    #[serde(default)]
+    pub accepted_statuses: Vec<u16>,
+    #[serde(default)]
+    pub body_match: Option<BodyMatch>,
+}
+
+/// Syarat isi body response, ditulis sebagai `{"contains": ".."}` atau `{"regex": ".."}`
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum BodyMatch {
+    Contains(String),
+    Regex(String),
+}
+
+/// Sumber pemakaian CPU node, dipilih melalui field `source`
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(
+    tag = "source",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum CpuSourceConfig {
+    /// Query `prometheus.cpuQuery` pada `prometheus.url`
+    #[default]
+    Prometheus,
+    /// `/stats/summary` milik kubelet melalui proxy API server
+    KubeletSummary,
+    /// NodeMetrics dari API `metrics.k8s.io`
+    MetricsApi,
+    /// `/proc/stat` lokal, hanya mengukur node tempat prober berjalan
+    ProcStat {
+        #[serde(default = "default_proc_stat_path")]
+        path: String,
+    },
+}
+
+/// Sumber tekanan memori node, dipilih melalui field `source`
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(
+    tag = "source",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum MemorySourceConfig {
+    /// Query `prometheus.memoryQuery` pada `prometheus.url`
+    #[default]
+    Prometheus,
+    /// `/proc/meminfo` lokal, hanya mengukur node tempat prober berjalan
+    ProcMeminfo {
+        #[serde(default = "default_proc_meminfo_path")]
+        path: String,
+    },
+    /// PSI `/proc/pressure/memory` lokal, hanya mengukur node tempat prober berjalan
+    ProcPressure {
+        #[serde(default = "default_proc_pressure_path")]
+        path: String,
+    },
+}
+
+/// Sumber saturasi jaringan node, dipilih melalui field `source`
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(
+    tag = "source",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum NetworkSourceConfig {
+    /// Query `prometheus.networkUtilizationQuery` dan `prometheus.networkErrorQuery`
+    #[default]
+    Prometheus,
+    /// Counter `/sys/class/net` lokal, hanya mengukur node tempat prober berjalan
+    SysClassNet {
+        #[serde(default = "default_sys_class_net_path")]
+        path: String,
+    },
+}
+
+/// Sumber pemakaian CPU pod Service, dipilih melalui field `source`
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(
+    tag = "source",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum PodCpuSourceConfig {
+    /// Metrik cAdvisor melalui query `prometheus.podCpuUsageQuery` dan
+    /// `prometheus.podCpuThrottlingQuery`
+    #[default]
+    Prometheus,
+    /// `/stats/summary` milik kubelet dibandingkan dengan limit CPU pada spec Pod,
+    /// tidak menyediakan data throttling
+    KubeletSummary,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LatencyStatistic {
+    #[default]
+    P50,
+    P90,
+    P99,
+    TrimmedMean,
+}
+
+/// Konfigurasi klien HTTP yang digunakan bersama oleh seluruh probe latency
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpProbeConfig {
+    /// Batas waktu sebuah request probe dalam milidetik, termasuk membaca response
+    #[serde(default = "default_probe_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Batas waktu membuka koneksi TCP dalam milidetik, juga berlaku pada probe mode `tcp`
+    #[serde(default = "default_probe_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    /// Koneksi idle yang dipertahankan untuk setiap endpoint, 0 membuka koneksi baru
+    /// pada setiap probe sehingga waktu handshake ikut terukur
+    #[serde(default)]
+    pub pool_max_idle_per_host: usize,
+    #[serde(default = "default_probe_user_agent")]
+    pub user_agent: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -54,10 +324,136 @@ pub struct ProbeConfig {
 pub struct AlphaConfig {
     pub ewma_latency: f64,
     pub ewma_cpu: f64,
+    #[serde(default = "default_ewma_rtt")]
+    pub ewma_rtt: f64,
+    #[serde(default = "default_ewma_memory")]
+    pub ewma_memory: f64,
+    #[serde(default = "default_ewma_network")]
+    pub ewma_network: f64,
+    #[serde(default = "default_ewma_pod_cpu")]
+    pub ewma_pod_cpu: f64,
     #[serde(default = "default_score_exponent")]
     pub score_exponent: f64,
 }
 
+/// Kebijakan perhitungan skor node, dipilih melalui field `policy`
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(
+    tag = "policy",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ScoringConfig {
+    /// `((1 - cpu) * (1 - memory) * (1 - network) * (1 - podCpu) / latency)^alpha.scoreExponent`
+    #[default]
+    PowerLaw,
+    WeightedLinear {
+        #[serde(default = "default_weight")]
+        weight_latency: f64,
+        #[serde(default = "default_weight")]
+        weight_cpu: f64,
+        /// Bobot RTT jaringan (ICMP), 0 berarti jarak jaringan diabaikan
+        #[serde(default)]
+        weight_rtt: f64,
+        /// Bobot memori yang tersedia (`1 - tekanan memori`), 0 berarti memori diabaikan
+        #[serde(default)]
+        weight_memory: f64,
+        /// Bobot kapasitas jaringan yang tersisa (`1 - saturasi jaringan`)
+        #[serde(default)]
+        weight_network: f64,
+        /// Bobot CPU yang tersisa pada pod Service (`1 - saturasi CPU pod`)
+        #[serde(default)]
+        weight_pod_cpu: f64,
+    },
+    InverseLatency,
+    Softmax {
+        #[serde(default = "default_softmax_temperature")]
+        temperature: f64,
+    },
+}
+
+/// Peredam perubahan porsi traffic antar tick, nilai bawaan langsung menerapkan porsi target
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DampeningConfig {
+    /// Pergeseran porsi node (0 hingga 1) yang harus dilampaui sebelum bobot ditulis ulang
+    #[serde(default)]
+    pub share_threshold: f64,
+    /// Perubahan porsi maksimum (0 hingga 1) sebuah node dalam satu tick
+    #[serde(default = "default_max_share_step")]
+    pub max_share_step: f64,
+}
+
+/// Batas atas setiap sinyal agar sebuah node layak menerima traffic Service, batas yang
+/// bernilai `null` tidak diperiksa
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EligibilityConfig {
+    /// Latency Service dalam milidetik
+    #[serde(default)]
+    pub max_latency_ms: Option<f64>,
+    /// Pemakaian CPU node (0 hingga 1)
+    #[serde(default = "default_max_cpu")]
+    pub max_cpu: Option<f64>,
+    /// Tekanan memori node (0 hingga 1)
+    #[serde(default = "default_max_memory")]
+    pub max_memory: Option<f64>,
+    /// Saturasi jaringan node (0 hingga 1)
+    #[serde(default)]
+    pub max_network: Option<f64>,
+    /// Saturasi CPU pod Service pada node (0 hingga 1)
+    #[serde(default)]
+    pub max_pod_cpu: Option<f64>,
+    /// RTT ICMP menuju node dalam milidetik
+    #[serde(default)]
+    pub max_rtt_ms: Option<f64>,
+    /// Umur maksimum sinyal latency dan CPU dalam detik
+    #[serde(default)]
+    pub max_staleness: Option<u64>,
+    /// Perlakuan Service ketika kurang dari dua node yang layak, default keepLastKnown
+    /// sehingga bobot terakhir tetap diterapkan sampai node kembali layak
+    #[serde(default)]
+    pub fallback: FallbackPolicy,
+}
+
+/// Perlakuan Service ketika kurang dari dua node berada di dalam jendela kelayakan
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum FallbackPolicy {
+    /// Traffic dibagi rata kepada seluruh node yang telah terukur dan tidak di-eject
+    EqualWeights,
+    /// Porsi yang terakhir diterapkan dipertahankan, namun node yang di-eject, keluar dari
+    /// cluster atau sinyalnya usang dikeluarkan dan porsinya dibagi kepada node lain
+    #[default]
+    KeepLastKnown,
+    /// Aturan Service dilepas sehingga traffic kembali diproses oleh kube-proxy
+    KubeProxy,
+}
+
+/// Ejection node yang gagal merespon probe latency sebuah Service
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlierConfig {
+    /// Jumlah kegagalan probe berturut-turut yang memicu ejection
+    #[serde(default = "default_consecutive_failures")]
+    pub consecutive_failures: u32,
+    /// Rasio kegagalan (0 hingga 1) pada jendela probe yang memicu ejection
+    #[serde(default = "default_failure_rate_threshold")]
+    pub failure_rate_threshold: f64,
+    /// Jumlah probe terakhir yang digunakan untuk menghitung rasio kegagalan, minimal 1
+    #[serde(default = "default_failure_rate_window")]
+    pub failure_rate_window: usize,
+    /// Durasi ejection pertama dalam detik, berlipat ganda untuk setiap ejection berulang
+    #[serde(default = "default_base_ejection_time")]
+    pub base_ejection_time: u64,
+    /// Batas atas durasi ejection dalam detik
+    #[serde(default = "default_max_ejection_time")]
+    pub max_ejection_time: u64,
+    /// Jumlah probe berhasil berturut-turut setelah masa ejection untuk diterima kembali
+    #[serde(default = "default_readmit_successes")]
+    pub readmit_successes: u32,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct MetricsConfig {
@@ -67,10 +463,203 @@ pub struct MetricsConfig {
     pub enabled: bool,
 }
 
+fn default_ewma_rtt() -> f64 {
+    0.2
+}
+
+fn default_ewma_memory() -> f64 {
+    0.3
+}
+
+fn default_ewma_network() -> f64 {
+    0.3
+}
+
+fn default_ewma_pod_cpu() -> f64 {
+    0.3
+}
+
+fn default_max_cpu() -> Option<f64> {
+    Some(0.95)
+}
+
+fn default_max_memory() -> Option<f64> {
+    Some(0.95)
+}
+
+fn default_pod_cpu_interval() -> u64 {
+    10
+}
+
+fn default_network_interval() -> u64 {
+    10
+}
+
+fn default_sys_class_net_path() -> String {
+    "/sys/class/net".to_string()
+}
+
+fn default_memory_interval() -> u64 {
+    10
+}
+
+fn default_proc_meminfo_path() -> String {
+    "/proc/meminfo".to_string()
+}
+
+fn default_proc_pressure_path() -> String {
+    "/proc/pressure/memory".to_string()
+}
+
+fn default_cpu_query() -> String {
+    // thanks to https://stackoverflow.com/a/66263640
+    r#"1 - avg by (instance) (irate(node_cpu_seconds_total{mode="idle"}[5m]))"#.to_string()
+}
+
+fn default_memory_query() -> String {
+    "1 - node_memory_MemAvailable_bytes / node_memory_MemTotal_bytes".to_string()
+}
+
+fn default_network_utilization_query() -> String {
+    // arah receive dan transmit dibedakan melalui label agar keduanya dapat dibandingkan
+    concat!(
+        r#"max by (instance) (("#,
+        r#"label_replace(rate(node_network_receive_bytes_total{device!~"lo|veth.*|cali.*|cni.*|flannel.*"}[1m]), "direction", "receive", "", "")"#,
+        r#" or "#,
+        r#"label_replace(rate(node_network_transmit_bytes_total{device!~"lo|veth.*|cali.*|cni.*|flannel.*"}[1m]), "direction", "transmit", "", "")"#,
+        r#") / on (instance, device) group_left (node_network_speed_bytes > 0))"#,
+    )
+    .to_string()
+}
+
+fn default_network_error_query() -> String {
+    concat!(
+        r#"sum by (instance) ("#,
+        r#"rate(node_network_receive_drop_total{device!~"lo|veth.*|cali.*|cni.*|flannel.*"}[1m])"#,
+        r#" + rate(node_network_transmit_drop_total{device!~"lo|veth.*|cali.*|cni.*|flannel.*"}[1m])"#,
+        r#" + rate(node_network_receive_errs_total{device!~"lo|veth.*|cali.*|cni.*|flannel.*"}[1m])"#,
+        r#" + rate(node_network_transmit_errs_total{device!~"lo|veth.*|cali.*|cni.*|flannel.*"}[1m])"#,
+        r#") / (sum by (instance) ("#,
+        r#"rate(node_network_receive_packets_total{device!~"lo|veth.*|cali.*|cni.*|flannel.*"}[1m])"#,
+        r#" + rate(node_network_transmit_packets_total{device!~"lo|veth.*|cali.*|cni.*|flannel.*"}[1m])"#,
+        r#") > 0)"#,
+    )
+    .to_string()
+}
+
+fn default_pod_cpu_usage_query() -> String {
+    concat!(
+        r#"sum by (namespace, pod) (rate(container_cpu_usage_seconds_total{container!="",pod=~"{{pods}}"}[1m]))"#,
+        r#" / sum by (namespace, pod) (container_spec_cpu_quota{container!="",pod=~"{{pods}}"}"#,
+        r#" / container_spec_cpu_period{container!="",pod=~"{{pods}}"})"#,
+    )
+    .to_string()
+}
+
+fn default_pod_cpu_throttling_query() -> String {
+    concat!(
+        r#"sum by (namespace, pod) (rate(container_cpu_cfs_throttled_periods_total{container!="",pod=~"{{pods}}"}[1m]))"#,
+        r#" / (sum by (namespace, pod) (rate(container_cpu_cfs_periods_total{container!="",pod=~"{{pods}}"}[1m])) > 0)"#,
+    )
+    .to_string()
+}
+
+fn default_join_label() -> String {
+    "instance".to_string()
+}
+
+fn default_proc_stat_path() -> String {
+    "/proc/stat".to_string()
+}
+
+fn default_icmp_interval() -> u64 {
+    10
+}
+
+fn default_icmp_timeout_ms() -> u64 {
+    1000
+}
+
 fn default_score_exponent() -> f64 {
     0.3
 }
 
+fn default_weight() -> f64 {
+    0.5
+}
+
+fn default_softmax_temperature() -> f64 {
+    0.1
+}
+
+fn default_max_share_step() -> f64 {
+    1.0
+}
+
+fn default_consecutive_failures() -> u32 {
+    5
+}
+
+fn default_failure_rate_threshold() -> f64 {
+    0.5
+}
+
+fn default_failure_rate_window() -> usize {
+    10
+}
+
+fn default_base_ejection_time() -> u64 {
+    30
+}
+
+fn default_max_ejection_time() -> u64 {
+    300
+}
+
+fn default_readmit_successes() -> u32 {
+    3
+}
+
+fn default_latency_samples() -> usize {
+    1
+}
+
+fn default_trim_fraction() -> f64 {
+    0.1
+}
+
+fn default_failure_penalty_ms() -> f64 {
+    2000.0
+}
+
+fn default_probe_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_probe_connect_timeout_ms() -> u64 {
+    500
+}
+
+fn default_probe_user_agent() -> String {
+    "proberv2".to_string()
+}
+
+fn default_check_path() -> String {
+    "/".to_string()
+}
+
+fn default_check_method() -> String {
+    "GET".to_string()
+}
+
+fn default_set_allowed_node_ipv6s() -> String {
+    "nodeport-ipv6s".to_string()
+}
+
+fn default_service_annotation() -> Option<String> {
+    Some("latency-aware/enabled".to_string())
+}
+
 fn default_listen_addr() -> String {
     "0.0.0.0:9101".to_string()
 }
@@ -87,3 +676,64 @@ impl Default for MetricsConfig {
         }
     }
 }
+
+impl Default for HttpProbeConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_probe_timeout_ms(),
+            connect_timeout_ms: default_probe_connect_timeout_ms(),
+            pool_max_idle_per_host: 0,
+            user_agent: default_probe_user_agent(),
+        }
+    }
+}
+
+impl Default for HttpCheckConfig {
+    fn default() -> Self {
+        Self {
+            path: default_check_path(),
+            method: default_check_method(),
+            headers: BTreeMap::new(),
+            body: None,
+            accepted_statuses: Vec::new(),
+            body_match: None,
+        }
+    }
+}
+
+impl Default for DampeningConfig {
+    fn default() -> Self {
+        Self {
+            share_threshold: 0.0,
+            max_share_step: default_max_share_step(),
+        }
+    }
+}
+
+impl Default for EligibilityConfig {
+    fn default() -> Self {
+        Self {
+            max_latency_ms: None,
+            max_cpu: default_max_cpu(),
+            max_memory: default_max_memory(),
+            max_network: None,
+            max_pod_cpu: None,
+            max_rtt_ms: None,
+            max_staleness: None,
+            fallback: FallbackPolicy::default(),
+        }
+    }
+}
+
+impl Default for OutlierConfig {
+    fn default() -> Self {
+        Self {
+            consecutive_failures: default_consecutive_failures(),
+            failure_rate_threshold: default_failure_rate_threshold(),
+            failure_rate_window: default_failure_rate_window(),
+            base_ejection_time: default_base_ejection_time(),
+            max_ejection_time: default_max_ejection_time(),
+            readmit_successes: default_readmit_successes(),
+        }
+    }
+}
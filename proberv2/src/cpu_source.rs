@@ -0,0 +1,369 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, bail};
+use axum::http;
+use futures::future::{BoxFuture, join_all};
+use k8s_openapi::api::core::v1::Node;
+use kube::{Api, ResourceExt, api::ListParams};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{
+    actor::WorkerNode,
+    config::{Config, CpuSourceConfig, PrometheusJoin},
+};
+
+/// Sumber pemakaian CPU worker node
+pub trait CpuSource: Send + Sync {
+    /// Pemakaian CPU (0 hingga 1) setiap node yang berhasil dibaca, Node Name -> pemakaian.
+    /// Node yang gagal dibaca tidak disertakan sehingga skor sebelumnya tetap digunakan
+    fn cpu_usage<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>>;
+}
+
+/// Satu query PromQL untuk seluruh node, series hasil query dicocokkan dengan node
+/// melalui label `join_label`
+pub struct Prometheus {
+    pub client: prometheus_http_query::Client,
+    pub query: String,
+    pub join_label: String,
+    pub join_by: PrometheusJoin,
+}
+
+/// Endpoint `/stats/summary` milik kubelet melalui proxy API server, dibandingkan
+/// dengan kapasitas CPU pada status Node
+pub struct KubeletSummary {
+    pub client: kube::Client,
+}
+
+/// API `metrics.k8s.io` (metrics-server), dibandingkan dengan kapasitas CPU pada status Node
+pub struct MetricsApi {
+    pub client: kube::Client,
+}
+
+/// Counter `/proc/stat` milik node tempat prober berjalan, pemakaian dihitung dari
+/// selisih counter terhadap pembacaan sebelumnya
+pub struct ProcStat {
+    pub path: PathBuf,
+    pub node_name: String,
+    // (idle, total) jiffies pada pembacaan sebelumnya
+    previous: Mutex<Option<(u64, u64)>>,
+}
+
+impl ProcStat {
+    pub fn new(path: impl Into<PathBuf>, node_name: String) -> Self {
+        Self {
+            path: path.into(),
+            node_name,
+            previous: Mutex::new(None),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    node: SummaryNode,
+}
+
+#[derive(Deserialize)]
+struct SummaryNode {
+    cpu: SummaryCpu,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryCpu {
+    usage_nano_cores: u64,
+}
+
+#[derive(Deserialize)]
+struct NodeMetricsList {
+    items: Vec<NodeMetrics>,
+}
+
+#[derive(Deserialize)]
+struct NodeMetrics {
+    metadata: NodeMetricsMetadata,
+    usage: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct NodeMetricsMetadata {
+    name: String,
+}
+
+impl CpuSource for Prometheus {
+    fn cpu_usage<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>> {
+        Box::pin(self.query_nodes(nodes))
+    }
+}
+
+impl Prometheus {
+    /// Mengevaluasi query untuk seluruh node, Node Name -> nilai series yang cocok
+    pub async fn query_nodes(&self, nodes: &[WorkerNode]) -> anyhow::Result<HashMap<String, f64>> {
+        // seluruh node dibaca melalui satu query sehingga hanya terdapat satu round trip
+        let query = self.query.replace("{{targets}}", &self.targets(nodes));
+        let response = self.client.query(query).get().await?;
+        let Some(datas) = response.data().as_vector() else {
+            bail!("promql result is not a vector: {response:?}");
+        };
+
+        let mut value_by_nodename = HashMap::new();
+        for data in datas {
+            let Some(value) = data.metric().get(&self.join_label) else {
+                continue;
+            };
+            let Some(worker) = nodes.iter().find(|worker| self.joins(worker, value)) else {
+                continue;
+            };
+            if value_by_nodename
+                .insert(worker.name.clone(), data.sample().value())
+                .is_some()
+            {
+                warn!(
+                    "actor: multiple promql series joined to node {}",
+                    worker.name
+                );
+            }
+        }
+        for worker in nodes {
+            if !value_by_nodename.contains_key(&worker.name) {
+                warn!("actor: empty promql result for node {}", worker.name);
+            }
+        }
+        Ok(value_by_nodename)
+    }
+
+    /// Nilai node yang dicocokkan dengan label join
+    fn join_value(&self, worker: &WorkerNode) -> String {
+        match self.join_by {
+            // alamat IPv6 ditulis dengan kurung siku sesuai format label instance
+            PrometheusJoin::Ip => match worker.ip {
+                IpAddr::V4(ip) => ip.to_string(),
+                IpAddr::V6(ip) => format!("[{ip}]"),
+            },
+            PrometheusJoin::Hostname => worker.hostname.clone(),
+            PrometheusJoin::NodeName => worker.name.clone(),
+        }
+    }
+
+    /// Regex alternatif nilai join seluruh node untuk `{{targets}}`, dengan escape
+    /// backslash agar tetap valid di dalam string PromQL
+    fn targets(&self, nodes: &[WorkerNode]) -> String {
+        let targets: Vec<String> = nodes
+            .iter()
+            .map(|worker| regex::escape(&self.join_value(worker)).replace('\\', "\\\\"))
+            .collect();
+        format!("({})", targets.join("|"))
+    }
+
+    /// Apakah nilai label join menunjuk kepada node tersebut, port pada nilai label diabaikan
+    fn joins(&self, worker: &WorkerNode, value: &str) -> bool {
+        match self.join_by {
+            PrometheusJoin::Ip => value
+                .parse::<SocketAddr>()
+                .map(|address| address.ip())
+                .or_else(|_| value.trim_start_matches('[').trim_end_matches(']').parse())
+                .is_ok_and(|ip| ip == worker.ip),
+            _ => {
+                let host = match value.rsplit_once(':') {
+                    Some((host, port)) if port.parse::<u16>().is_ok() => host,
+                    _ => value,
+                };
+                host == self.join_value(worker)
+            }
+        }
+    }
+}
+
+impl CpuSource for KubeletSummary {
+    fn cpu_usage<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>> {
+        Box::pin(async move {
+            let capacity_by_nodename = cpu_capacity(&self.client).await?;
+            let summaries = join_all(nodes.iter().map(|worker| async move {
+                let url = format!("/api/v1/nodes/{}/proxy/stats/summary", worker.name);
+                let request = http::Request::get(url).body(Vec::new())?;
+                let summary = self.client.request::<Summary>(request).await?;
+                anyhow::Ok(summary.node.cpu.usage_nano_cores)
+            }))
+            .await;
+
+            let mut usage_by_nodename = HashMap::new();
+            for (worker, summary) in nodes.iter().zip(summaries) {
+                let usage_nano_cores = match summary {
+                    Ok(usage_nano_cores) => usage_nano_cores,
+                    Err(e) => {
+                        warn!(
+                            "actor: failed to read kubelet summary of node {}: {e}",
+                            worker.name
+                        );
+                        continue;
+                    }
+                };
+                let Some(capacity) = capacity_by_nodename.get(&worker.name) else {
+                    warn!("actor: node {} has no cpu capacity", worker.name);
+                    continue;
+                };
+                usage_by_nodename.insert(
+                    worker.name.clone(),
+                    usage_nano_cores as f64 / 1e9 / capacity,
+                );
+            }
+            Ok(usage_by_nodename)
+        })
+    }
+}
+
+impl CpuSource for MetricsApi {
+    fn cpu_usage<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>> {
+        Box::pin(async move {
+            let capacity_by_nodename = cpu_capacity(&self.client).await?;
+            // seluruh node dibaca dalam satu request
+            let request =
+                http::Request::get("/apis/metrics.k8s.io/v1beta1/nodes").body(Vec::new())?;
+            let metrics = self.client.request::<NodeMetricsList>(request).await?;
+            let usage_by_name: HashMap<String, String> = metrics
+                .items
+                .into_iter()
+                .filter_map(|item| {
+                    let cpu = item.usage.get("cpu")?.clone();
+                    Some((item.metadata.name, cpu))
+                })
+                .collect();
+
+            let mut usage_by_nodename = HashMap::new();
+            for worker in nodes {
+                let Some(usage) = usage_by_name.get(&worker.name) else {
+                    warn!("actor: node {} has no metrics.k8s.io usage", worker.name);
+                    continue;
+                };
+                let Some(usage) = parse_cpu_quantity(usage) else {
+                    warn!("actor: invalid cpu usage {usage} of node {}", worker.name);
+                    continue;
+                };
+                let Some(capacity) = capacity_by_nodename.get(&worker.name) else {
+                    warn!("actor: node {} has no cpu capacity", worker.name);
+                    continue;
+                };
+                usage_by_nodename.insert(worker.name.clone(), usage / capacity);
+            }
+            Ok(usage_by_nodename)
+        })
+    }
+}
+
+impl CpuSource for ProcStat {
+    fn cpu_usage<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>> {
+        Box::pin(async move {
+            let mut usage_by_nodename = HashMap::new();
+            // hanya node tempat prober berjalan yang dapat dibaca secara lokal
+            if !nodes.iter().any(|worker| worker.name == self.node_name) {
+                return Ok(usage_by_nodename);
+            }
+
+            let stat = tokio::fs::read_to_string(&self.path).await?;
+            let (idle, total) = parse_proc_stat(&stat)?;
+            let previous = self
+                .previous
+                .lock()
+                .map_err(|_| anyhow!("proc stat state poisoned"))?
+                .replace((idle, total));
+
+            // pembacaan pertama hanya menjadi acuan bagi pembacaan berikutnya
+            if let Some((previous_idle, previous_total)) = previous
+                && total > previous_total
+            {
+                let idle = idle.saturating_sub(previous_idle) as f64;
+                let total = (total - previous_total) as f64;
+                usage_by_nodename.insert(self.node_name.clone(), 1.0 - idle / total);
+            }
+            Ok(usage_by_nodename)
+        })
+    }
+}
+
+/// Sumber pemakaian CPU yang dipilih pada konfigurasi
+pub async fn cpu_source(config: &Config) -> anyhow::Result<Box<dyn CpuSource>> {
+    Ok(match &config.probe.cpu_source {
+        CpuSourceConfig::Prometheus => Box::new(Prometheus {
+            client: prometheus_http_query::Client::try_from(config.prometheus.url.clone())?,
+            query: config.prometheus.cpu_query.clone(),
+            join_label: config.prometheus.join_label.clone(),
+            join_by: config.prometheus.join_by,
+        }),
+        CpuSourceConfig::KubeletSummary => Box::new(KubeletSummary {
+            client: kube::Client::try_default().await?,
+        }),
+        CpuSourceConfig::MetricsApi => Box::new(MetricsApi {
+            client: kube::Client::try_default().await?,
+        }),
+        CpuSourceConfig::ProcStat { path } => {
+            Box::new(ProcStat::new(path, config.kubernetes.node_name.clone()))
+        }
+    })
+}
+
+/// Kapasitas CPU setiap node dalam satuan core, Node Name -> core
+async fn cpu_capacity(client: &kube::Client) -> anyhow::Result<HashMap<String, f64>> {
+    let nodes = Api::<Node>::all(client.clone())
+        .list(&ListParams::default())
+        .await?;
+    Ok(nodes
+        .into_iter()
+        .filter_map(|node| {
+            let capacity = node.status.as_ref()?.capacity.as_ref()?.get("cpu")?;
+            let capacity = parse_cpu_quantity(&capacity.0).filter(|capacity| *capacity > 0.0)?;
+            Some((node.name_any(), capacity))
+        })
+        .collect())
+}
+
+/// Mengubah quantity CPU Kubernetes (`2`, `1500m`, `250000000n`) menjadi satuan core
+pub fn parse_cpu_quantity(quantity: &str) -> Option<f64> {
+    let (value, scale) = match quantity.char_indices().last()? {
+        (idx, 'n') => (&quantity[..idx], 1e-9),
+        (idx, 'u') => (&quantity[..idx], 1e-6),
+        (idx, 'm') => (&quantity[..idx], 1e-3),
+        (idx, 'k') => (&quantity[..idx], 1e3),
+        _ => (quantity, 1.0),
+    };
+    value.parse::<f64>().ok().map(|value| value * scale)
+}
+
+/// Membaca jiffies (idle, total) pada baris `cpu` agregat `/proc/stat`,
+/// idle mencakup kolom idle dan iowait
+pub fn parse_proc_stat(stat: &str) -> anyhow::Result<(u64, u64)> {
+    let Some(line) = stat.lines().find(|line| line.starts_with("cpu ")) else {
+        bail!("aggregate cpu line not found");
+    };
+    let fields = line
+        .split_whitespace()
+        .skip(1)
+        .map(str::parse::<u64>)
+        .collect::<Result<Vec<_>, _>>()?;
+    if fields.len() < 5 {
+        bail!("aggregate cpu line has only {} fields", fields.len());
+    }
+    // guest dan guest_nice telah termasuk dalam user dan nice
+    let total = fields.iter().take(8).sum();
+    Ok((fields[3] + fields[4], total))
+}
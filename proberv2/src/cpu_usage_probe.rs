@@ -1,118 +1,29 @@
-use std::collections::{HashMap, HashSet};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    actor::{Event, EwmaDatapoint, WorkerNode},
+    actor::{Event, EwmaDatapoint},
     config::Config,
+    cpu_source::cpu_source,
     metrics,
+    node_signal_probe::{NodeSignal, probe_node_signal},
 };
-use prometheus_http_query::Client;
-use tokio::{
-    sync::broadcast,
-    time::{Duration, interval},
-};
-use tokio_util::sync::CancellationToken;
-use tracing::{error, info, warn};
 
+/// Mengukur pemakaian CPU setiap worker node, sinyal utama skor node bersama latency
 pub async fn probe_cpu_usage(
     config: Config,
     tx: broadcast::Sender<Event>,
     token: CancellationToken,
 ) -> anyhow::Result<()> {
-    let mut ticker = interval(Duration::from_secs(config.probe.cpu_interval));
-    let mut nodes = HashSet::<WorkerNode>::new();
-    let mut datapoint_by_nodename = HashMap::<String, f64>::new();
-
-    let mut rx = tx.subscribe();
-    'main: loop {
-        // hentikan main loop ketika program shutdown
-        if token.is_cancelled() {
-            info!("actor: exiting probe_cpu_usage task");
-            return Ok(());
-        }
-
-        // mencoba membaca event penambahan Node baru dari channel,
-        // jika tidak ada lanjut ke baris selanjutnya
-        while let Ok(event) = rx.try_recv() {
-            if let Event::NodeJoined(node) = event {
-                nodes.insert(node);
-            }
-        }
-
-        for worker in &nodes {
-            // membuat client Prometheus
-            let client = match Client::try_from(config.prometheus.url.clone()) {
-                Ok(client) => client,
-                Err(e) => {
-                    error!("actor: failed to create prometheus client: {e}");
-                    continue;
-                }
-            };
-
-            // membuat query PromQL untuk membaca persentase pemakaian CPU pada worker node
-            let query = format!(
-                // thanks to https://stackoverflow.com/a/66263640
-                r#"(1 - avg(irate(node_cpu_seconds_total{{mode="idle",instance="{}:9100"}}[5m])) by (instance))"#,
-                worker.ip,
-            );
-
-            // mengeksekusi query untuk membaca persentase pemakaian CPU dari worker node
-            let response = match client.query(query).get().await {
-                Ok(response) => response,
-                Err(e) => {
-                    error!(
-                        "actor: failed to query node {} cpu usage: {}",
-                        worker.name, e
-                    );
-                    continue;
-                }
-            };
-            let datas = match response.data().as_vector() {
-                Some(datas) => datas,
-                None => {
-                    warn!("actor: promql result is not a vector: {:?}", response);
-                    continue;
-                }
-            };
-            let Some(data) = datas.first() else {
-                warn!("actor: empty promql result");
-                continue;
-            };
-            let cpu_usage = data.sample().value();
-
-            // menulis metrik CPU mentah (pre-EWMA) ke Prometheus
-            metrics::set_raw_cpu_usage(&worker.name, cpu_usage);
-
-            // membaca alpha untuk perhitungan EWMA CPU
-            let alpha = config.alpha.ewma_cpu;
-
-            let datapoint = match datapoint_by_nodename.get(&worker.name) {
-                // kalkulasi skor EWMA ketika terdapat skor pada titik sebelumnya
-                Some(datapoint) => alpha * cpu_usage + (1.0 - alpha) * *datapoint,
-                // gunakan persentase pengunaan CPU mentah sebagai skor EWMA
-                // ketika tidak ada skor pada titik penghitungan sebelumnya
-                None => cpu_usage,
-            };
-
-            // menyimpan nilai skor EWMA per node untuk digunakan pada perhitungan skor selanjutnya
-            datapoint_by_nodename.insert(worker.name.clone(), datapoint);
-
-            // menulis metrik EWMA CPU ke Prometheus
-            metrics::set_ewma_cpu(&worker.name, datapoint);
-
-            // mengirim hasil skor EWMA untuk metrik penggunaan CPU untuk
-            // setiap node sebagai event EwmaCalculated melalui channel
-            if let Err(e) = tx.send(Event::EwmaCalculated(
-                worker.name.clone(),
-                EwmaDatapoint::Cpu(datapoint),
-            )) {
-                info!("actor: latency probe exiting: {e}");
-                break 'main;
-            };
-        }
-
-        // memberhentikan eksekusi loop dalam kurun waktu yang ditentukan
-        ticker.tick().await;
-    }
-
-    Ok(())
+    let signal = NodeSignal {
+        name: "cpu usage",
+        interval: config.probe.cpu_interval,
+        alpha: config.alpha.ewma_cpu,
+        source: cpu_source(&config).await?,
+        read: |source, nodes| source.cpu_usage(nodes),
+        set_raw: metrics::set_raw_cpu_usage,
+        set_ewma: metrics::set_ewma_cpu,
+        datapoint: EwmaDatapoint::Cpu,
+    };
+    probe_node_signal(signal, tx, token).await
 }
@@ -0,0 +1,46 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::config::DampeningConfig;
+
+/// Menentukan porsi traffic (0 hingga 1) setiap node, atau setiap endpoint di dalam porsi
+/// node-nya, yang akan diterapkan berdasarkan porsi target hasil scoring dan porsi yang
+/// terakhir diterapkan.
+///
+/// Porsi sebelumnya dipertahankan selama himpunan node tidak berubah dan tidak ada porsi
+/// node yang bergeser melebihi `shareThreshold`. Selain itu porsi setiap node bergerak menuju
+/// target paling banyak `maxShareStep` per tick, node baru memulai dari porsi 0
+pub fn dampen<K: Clone + Eq + Hash>(
+    previous: Option<&HashMap<K, f64>>,
+    target: HashMap<K, f64>,
+    config: &DampeningConfig,
+) -> HashMap<K, f64> {
+    let Some(previous) = previous else {
+        return target;
+    };
+
+    let same_nodes =
+        previous.len() == target.len() && target.keys().all(|node| previous.contains_key(node));
+    let max_shift = target
+        .iter()
+        .map(|(node, share)| (share - previous.get(node).copied().unwrap_or(0.0)).abs())
+        .fold(0.0, f64::max);
+    if same_nodes && max_shift <= config.share_threshold {
+        return previous.clone();
+    }
+
+    let mut shares: HashMap<K, f64> = target
+        .into_iter()
+        .map(|(node, share)| {
+            let current = previous.get(&node).copied().unwrap_or(0.0);
+            let step = (share - current).clamp(-config.max_share_step, config.max_share_step);
+            (node, current + step)
+        })
+        .collect();
+
+    // porsi node yang keluar serta pembatasan langkah membuat total porsi tidak lagi 1
+    let total: f64 = shares.values().sum();
+    if total > 0.0 {
+        shares.values_mut().for_each(|share| *share /= total);
+    }
+    shares
+}
@@ -1,27 +1,38 @@
-use kube::runtime;
-use std::{collections::HashMap, net::Ipv4Addr};
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+};
 use tokio_util::sync::CancellationToken;
 
-use futures::TryStreamExt;
+use futures::{StreamExt, TryStreamExt, stream::BoxStream};
 use k8s_openapi::{
-    api::core::v1::{EndpointSubset, Endpoints, Service as KubernetesService},
+    api::{
+        core::v1::Service as KubernetesService,
+        discovery::v1::{Endpoint as SliceEndpoint, EndpointSlice},
+    },
     apimachinery::pkg::util::intstr::IntOrString,
 };
 use kube::{
-    Api, Client, ResourceExt,
-    runtime::{WatchStreamExt, watcher::Config},
+    Api, Client, Resource, ResourceExt,
+    runtime::{self, WatchStreamExt, watcher},
 };
+use serde::de::DeserializeOwned;
 use tokio::sync::broadcast;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    actor::{Event, Service},
-    config::Config as AppConfig,
+    actor::{Endpoint, Event, Protocol, Service, ServicePort},
+    config::{Config as AppConfig, KubernetesConfig},
 };
 
-enum Control<E> {
-    Watcher(E),
-    Stop,
+/// Label yang dipasang oleh EndpointSlice controller untuk menunjuk Service pemilik slice
+const SERVICE_NAME_LABEL: &str = "kubernetes.io/service-name";
+
+/// Perubahan yang diterima dari salah satu watcher Service atau EndpointSlice, beserta
+/// namespace yang dipantau oleh watcher tersebut (`None` untuk seluruh namespace)
+enum Change {
+    Service(Option<String>, Box<watcher::Event<KubernetesService>>),
+    Slice(Option<String>, Box<watcher::Event<EndpointSlice>>),
 }
 
 pub async fn watch_endpoints(
@@ -30,122 +41,678 @@ pub async fn watch_endpoints(
     token: CancellationToken,
 ) -> anyhow::Result<()> {
     let client = Client::try_default().await?;
-    let endpoints_api: Api<Endpoints> =
-        Api::namespaced(client.clone(), &config.kubernetes.namespace);
-    let service_api: Api<KubernetesService> = Api::namespaced(client, &config.kubernetes.namespace);
-
-    // berlangganan perubahan endpoint aplikasi untuk menyesuaikan ip pod secara real-time
-    let handler = runtime::watcher(endpoints_api, Config::default().fields(&format!("metadata.name={}", config.kubernetes.service)))
-        .applied_objects()
-        .default_backoff()
-        .map_err(Control::Watcher)
-        .try_for_each(|endpoints| {
-            let tx = tx.clone();
-            let service_api = service_api.clone();
-            async move {
-                // mengambil nama Service dari Endpoints
-                let servicename = endpoints.name_any();
-                info!("actor: endpoints changes occured for {servicename} service");
-
-                // mengambil property addresses dari Endpoints yang merupakan 
-                // sekumpulan alamat IP dari pod aplikasi
-                let Some(EndpointSubset { addresses: Some(addresses), .. }) = endpoints
-                    .subsets
-                    .as_ref()
-                    .and_then(|subsets| subsets.first())
-                else {
-                    warn!("actor: empty subsets from endpointslice {servicename}");
-                    return Ok(());
-                };
 
-                // melakukan query untuk mendapatkan Service
-                // berdasarkan nama Service yang diperoleh dari Endpoints
-                let service = match service_api.get(&servicename).await {
-                    Ok(service) => service,
-                    Err(e) => {
-                        error!("actor: failed to get endpoints service object of {servicename}: {e}");
-                        return Ok(());
-                    }
-                };
+    // Service yang ikut dikendalikan dipilih melalui label selector (difilter oleh API server)
+    let mut service_watcher_config = watcher::Config::default();
+    if let Some(selector) = &config.kubernetes.label_selector {
+        service_watcher_config = service_watcher_config.labels(selector);
+    }
 
-                // mengambil port pertama dari property ports yang terdafar pada Service
-                let Some(port) = service.spec.as_ref()
-                    .and_then(|spec| spec.ports.as_ref())
-                    .and_then(|ports| ports.first()) else {
-                        warn!("actor: cannot find any ports for service {servicename}");
-                        return Ok(());
-                    };
-
-                // mengambil port NodePort
-                let Some(nodeport) = port.node_port else {
-                    warn!("actor: cannot find any ports for service {servicename}");
-                    return Ok(());
-                };
+    // berlangganan perubahan Service dan EndpointSlice pada setiap namespace yang dipantau,
+    // seluruh namespace dipantau jika tidak ada namespace yang dikonfigurasi
+    let mut streams = Vec::<BoxStream<'static, Result<Change, watcher::Error>>>::new();
+    for namespace in namespaces(&config) {
+        let service_api: Api<KubernetesService> = api(client.clone(), namespace);
+        let slice_api: Api<EndpointSlice> = api(client.clone(), namespace);
+        let scope = namespace.map(str::to_string);
+        streams.push(
+            runtime::watcher(service_api, service_watcher_config.clone())
+                .default_backoff()
+                .map_ok({
+                    let scope = scope.clone();
+                    move |event| Change::Service(scope.clone(), Box::new(event))
+                })
+                .boxed(),
+        );
+        // hanya EndpointSlice yang dimiliki oleh sebuah Service yang dipantau
+        streams.push(
+            runtime::watcher(
+                slice_api,
+                watcher::Config::default().labels(SERVICE_NAME_LABEL),
+            )
+            .default_backoff()
+            .map_ok(move |event| Change::Slice(scope.clone(), Box::new(event)))
+            .boxed(),
+        );
+    }
+    let mut stream = futures::stream::select_all(streams);
 
-                // mengambil target port yang dituju dari port NodePort
-                let targetport = match port.target_port {
-                    Some(IntOrString::Int(port)) => port,
-                    _ => port.port,
-                };
+    let mut cache = Cache::default();
+    // Service yang terakhir dikirim melalui channel, digunakan untuk mendeteksi
+    // Service yang perlu dilepas dan menghindari pengiriman ulang tanpa perubahan
+    let mut announced = HashMap::<String, Service>::new();
 
-                // inisialisasi map untuk pemetaan/grouping endpoints berdasarkan node
-                let mut endpoints_by_nodename = HashMap::<String, Vec<Ipv4Addr>>::new();
-
-                // melakukan pemetaan/grouping endpoints berdasarkan node
-                for address in addresses {
-                    let ip = match address.ip.clone().parse::<Ipv4Addr>() {
-                        Ok(ip) => ip,
-                        Err(e) => {
-                            error!("actor: invalid ipv4 string: {e}");
-                            continue;
-                        }
-                    };
-                    let Some(nodename) = &address.node_name else {
-                        warn!("actor: missing nodename for pod endpoint of {ip}");
-                        continue;
-                    };
-                    let mut endpoints = match endpoints_by_nodename.get(nodename) {
-                        Some(endpoints) => endpoints.clone(),
-                        None => Vec::new(),
-                    };
-                    endpoints.push(ip);
-                    endpoints_by_nodename.insert(nodename.clone(), endpoints);
-                }
+    loop {
+        // menunggu sinyal secara blocking diantara sinyal program shutdown atau event dari watcher
+        let change = tokio::select! {
+            _ = token.cancelled() => {
+                info!("actor: exiting endpoints_watch task");
+                return Ok(());
+            },
+            change = stream.try_next() => change?,
+        };
+        let Some(change) = change else {
+            return Ok(());
+        };
 
-                info!("actor: captured service {servicename} endpoints changes: {endpoints_by_nodename:?}");
+        let keys = match change {
+            Change::Service(scope, event) => {
+                cache.apply_service(&config.kubernetes, &scope, *event)
+            }
+            Change::Slice(scope, event) => cache.apply_slice(&scope, *event),
+        };
 
-                // lewati jika Endpoints hanya terdaftar pada 1 node
-                if endpoints_by_nodename.len() == 1 {
-                    info!("actor: skipping undistributed service {servicename} endpoints containing only 1 node");
-                    return Ok(());
+        for key in keys {
+            // Service yang tidak lagi terpilih, dihapus, atau tidak memiliki NodePort akan dilepas
+            let desired = cache.desired(&key);
+
+            let mut events = Vec::new();
+            match (announced.remove(&key), desired) {
+                (Some(previous), Some(service)) if previous == service => {
+                    announced.insert(key, previous);
+                    continue;
+                }
+                (None, None) => continue,
+                (Some(previous), None) => {
+                    info!("actor: service {key} is no longer steerable");
+                    events.push(Event::ServiceRemoved(previous));
                 }
+                (_, Some(service)) => {
+                    info!(
+                        "actor: captured service {key} endpoints changes: {:?}",
+                        service.endpoints_by_nodename
+                    );
+                    announced.insert(key, service.clone());
+                    events.push(Event::ServiceChanged(service));
+                }
+            }
 
-                // mengirim informasi penuh terkait sebuah Service (nama, NodePort, port target, kelompok endpoints berdasarkan node)
-                // sebagai event ServiceChanged melalui channel untuk dikonsumsi proses lain
-                let service = Service { name: servicename, nodeport, targetport, endpoints_by_nodename };
-                if let Err(e) = tx.send(Event::ServiceChanged(service)) {
-                    info!("actor: latency probe exiting: {e}");
-                    // memberhentikan langganan ketika gagal mengirim event NodeJoined pada channel
+            // mengirim informasi penuh terkait sebuah Service (nama, NodePort, port target, kelompok endpoints berdasarkan node)
+            // sebagai event ServiceChanged atau ServiceRemoved melalui channel untuk dikonsumsi proses lain
+            for event in events {
+                if let Err(e) = tx.send(event) {
+                    // memberhentikan langganan ketika gagal mengirim event pada channel
                     // yang berarti channel telah ditutup karena dalam proses program shutdown
-                    return Err(Control::Stop);
+                    info!("actor: stopping endpoints watcher: {e}");
+                    return Ok(());
                 };
+            }
+        }
+    }
+}
+
+/// Cache Service terpilih berdasarkan key <namespace>/<nama>, serta seluruh EndpointSlice
+/// milik Service tersebut berdasarkan nama slice
+#[derive(Default)]
+struct Cache {
+    services: HashMap<String, KubernetesService>,
+    slices: HashMap<String, HashMap<String, EndpointSlice>>,
+    // object yang diterima selama watcher melakukan list ulang
+    relisted_services: HashSet<String>,
+    relisted_slices: HashSet<(String, String)>,
+}
+
+impl Cache {
+    /// Menerapkan event watcher Service, mengembalikan key Service yang mungkin berubah
+    fn apply_service(
+        &mut self,
+        config: &KubernetesConfig,
+        scope: &Option<String>,
+        event: watcher::Event<KubernetesService>,
+    ) -> Vec<String> {
+        match event {
+            watcher::Event::Init => {
+                self.relisted_services.retain(|key| !in_scope(scope, key));
+                Vec::new()
+            }
+            watcher::Event::Apply(service) | watcher::Event::InitApply(service) => {
+                let key = object_key(&service);
+                self.relisted_services.insert(key.clone());
+                if is_selected(config, &service) {
+                    self.services.insert(key.clone(), service);
+                } else {
+                    self.services.remove(&key);
+                }
+                vec![key]
+            }
+            watcher::Event::Delete(service) => {
+                let key = object_key(&service);
+                self.services.remove(&key);
+                vec![key]
+            }
+            // Service yang tidak muncul pada list ulang dianggap telah dihapus
+            watcher::Event::InitDone => {
+                let departed: Vec<String> = self
+                    .services
+                    .keys()
+                    .filter(|key| in_scope(scope, key) && !self.relisted_services.contains(*key))
+                    .cloned()
+                    .collect();
+                for key in &departed {
+                    self.services.remove(key);
+                }
+                departed
+            }
+        }
+    }
+
+    /// Menerapkan event watcher EndpointSlice, mengembalikan key Service pemilik slice
+    /// yang mungkin berubah
+    fn apply_slice(
+        &mut self,
+        scope: &Option<String>,
+        event: watcher::Event<EndpointSlice>,
+    ) -> Vec<String> {
+        match event {
+            watcher::Event::Init => {
+                self.relisted_slices
+                    .retain(|(key, _)| !in_scope(scope, key));
+                Vec::new()
+            }
+            watcher::Event::Apply(slice) | watcher::Event::InitApply(slice) => {
+                let Some(key) = service_key(&slice) else {
+                    return Vec::new();
+                };
+                self.relisted_slices.insert((key.clone(), slice.name_any()));
+                self.slices
+                    .entry(key.clone())
+                    .or_default()
+                    .insert(slice.name_any(), slice);
+                vec![key]
+            }
+            // perubahan tetap diproses agar endpoint pada slice yang dihapus ikut dilepas
+            watcher::Event::Delete(slice) => {
+                let Some(key) = service_key(&slice) else {
+                    return Vec::new();
+                };
+                self.remove_slice(&key, &slice.name_any());
+                vec![key]
+            }
+            // slice yang tidak muncul pada list ulang dianggap telah dihapus
+            watcher::Event::InitDone => {
+                let departed: Vec<(String, String)> = self
+                    .slices
+                    .iter()
+                    .filter(|(key, _)| in_scope(scope, key))
+                    .flat_map(|(key, slice_by_name)| {
+                        slice_by_name.keys().map(|name| (key.clone(), name.clone()))
+                    })
+                    .filter(|slice| !self.relisted_slices.contains(slice))
+                    .collect();
+                for (key, name) in &departed {
+                    self.remove_slice(key, name);
+                }
+                departed.into_iter().map(|(key, _)| key).collect()
+            }
+        }
+    }
+
+    fn remove_slice(&mut self, key: &str, name: &str) {
+        if let Some(slice_by_name) = self.slices.get_mut(key) {
+            slice_by_name.remove(name);
+            if slice_by_name.is_empty() {
+                self.slices.remove(key);
+            }
+        }
+    }
+
+    /// Service yang seharusnya dikendalikan, `None` ketika Service tidak terpilih,
+    /// telah dihapus, atau tidak memiliki NodePort
+    fn desired(&self, key: &str) -> Option<Service> {
+        let kubernetes_service = self.services.get(key)?;
+        let slice_by_name = self.slices.get(key);
+        build_service(
+            kubernetes_service,
+            slice_by_name.into_iter().flat_map(|s| s.values()),
+        )
+    }
+}
+
+/// Memeriksa apakah key <namespace>/<nama> dipantau oleh watcher dengan namespace tersebut
+fn in_scope(scope: &Option<String>, key: &str) -> bool {
+    match scope {
+        Some(namespace) => key
+            .split_once('/')
+            .is_some_and(|(prefix, _)| prefix == namespace),
+        None => true,
+    }
+}
+
+fn namespaces(config: &AppConfig) -> Vec<Option<&str>> {
+    if config.kubernetes.namespaces.is_empty() {
+        return vec![None];
+    }
+    config
+        .kubernetes
+        .namespaces
+        .iter()
+        .map(|namespace| Some(namespace.as_str()))
+        .collect()
+}
+
+fn api<K>(client: Client, namespace: Option<&str>) -> Api<K>
+where
+    K: Resource<Scope = k8s_openapi::NamespaceResourceScope>,
+    <K as Resource>::DynamicType: Default,
+    K: Clone + DeserializeOwned + std::fmt::Debug,
+{
+    match namespace {
+        Some(namespace) => Api::namespaced(client, namespace),
+        None => Api::all(client),
+    }
+}
+
+/// Key <namespace>/<nama> dari Service pemilik sebuah EndpointSlice
+fn service_key(slice: &EndpointSlice) -> Option<String> {
+    let servicename = slice.labels().get(SERVICE_NAME_LABEL)?;
+    Some(format!(
+        "{}/{}",
+        slice.namespace().unwrap_or_default(),
+        servicename
+    ))
+}
+
+fn object_key<K: Resource>(object: &K) -> String {
+    format!(
+        "{}/{}",
+        object.meta().namespace.as_deref().unwrap_or_default(),
+        object.meta().name.as_deref().unwrap_or_default()
+    )
+}
+
+/// Service dianggap ikut serta ketika memiliki anotasi yang dikonfigurasi bernilai "true",
+/// atau cukup lolos label selector ketika anotasi tidak dikonfigurasi
+fn is_selected(config: &KubernetesConfig, service: &KubernetesService) -> bool {
+    match &config.annotation {
+        Some(annotation) => service
+            .annotations()
+            .get(annotation)
+            .is_some_and(|value| value == "true"),
+        None => true,
+    }
+}
+
+/// Endpoint hanya menerima koneksi baru ketika siap dan tidak sedang dihentikan, kondisi
+/// yang tidak diisi diperlakukan sesuai spesifikasi API (ready/serving = true, terminating = false)
+fn is_routable(endpoint: &SliceEndpoint) -> bool {
+    let Some(conditions) = &endpoint.conditions else {
+        return true;
+    };
+    conditions.ready.unwrap_or(true)
+        && conditions.serving.unwrap_or(true)
+        && !conditions.terminating.unwrap_or(false)
+}
+
+fn build_service<'a>(
+    service: &KubernetesService,
+    slices: impl Iterator<Item = &'a EndpointSlice> + Clone,
+) -> Option<Service> {
+    let namespace = service.namespace().unwrap_or_default();
+    let servicename = service.name_any();
+
+    // mengambil seluruh port yang terdaftar pada Service beserta NodePort-nya, Service
+    // dual-stack menggunakan NodePort yang sama untuk kedua family
+    let mut ports = Vec::new();
+    for port in service
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.ports.as_ref())
+        .into_iter()
+        .flatten()
+    {
+        let Some(nodeport) = port.node_port else {
+            continue;
+        };
+        let Some(protocol) = Protocol::parse(port.protocol.as_deref().unwrap_or("TCP")) else {
+            warn!(
+                "actor: unsupported protocol {:?} on service {namespace}/{servicename}",
+                port.protocol
+            );
+            continue;
+        };
 
-                Ok(())
+        // mengambil target port yang dituju dari port NodePort, EndpointSlice menyimpan
+        // hasil resolusi target port sehingga port bernama juga dapat digunakan. Port Service
+        // tanpa nama tercatat dengan nama "" pada EndpointSlice
+        let resolved = slices
+            .clone()
+            .flat_map(|slice| slice.ports.iter().flatten())
+            .find(|slice_port| {
+                slice_port.name.as_deref().unwrap_or_default()
+                    == port.name.as_deref().unwrap_or_default()
+                    && slice_port.protocol.as_deref().unwrap_or("TCP")
+                        == port.protocol.as_deref().unwrap_or("TCP")
+            })
+            .and_then(|slice_port| slice_port.port);
+        let targetport = match (resolved, &port.target_port) {
+            (Some(port), _) => port,
+            (None, Some(IntOrString::Int(port))) => *port,
+            (None, None) => port.port,
+            // target port bernama hanya dapat diketahui dari EndpointSlice
+            (None, Some(IntOrString::String(name))) => {
+                debug!(
+                    "actor: target port {name} of service {namespace}/{servicename} is not resolved yet"
+                );
+                continue;
             }
+        };
+
+        ports.push(ServicePort {
+            protocol,
+            nodeport,
+            targetport,
         });
+    }
+
+    if ports.is_empty() {
+        warn!("actor: cannot find any nodeport for service {namespace}/{servicename}");
+        return None;
+    }
 
-    // menunggu sinyal secara blocking diantara sinyal program shutdown atau
-    // langganan perubahan node berhenti untuk memberhentikan fungsi
-    tokio::select! {
-        _ = token.cancelled() => {
-            info!("actor: exiting endpoints_watch task");
-            Ok(())
-        },
-        result = handler => {
-            if let Err(Control::Watcher(e)) = result {
-                return Err(e.into());
+    // inisialisasi map untuk pemetaan/grouping endpoints berdasarkan node
+    let mut endpoints_by_nodename = HashMap::<String, Vec<Endpoint>>::new();
+
+    // melakukan pemetaan/grouping endpoints dari seluruh slice berdasarkan node
+    for slice in slices {
+        // slice dengan addressType FQDN tidak dapat dijadikan tujuan DNAT
+        if slice.address_type != "IPv4" && slice.address_type != "IPv6" {
+            continue;
+        }
+        for endpoint in &slice.endpoints {
+            let Some(address) = endpoint.addresses.first() else {
+                continue;
+            };
+            let ip = match address.parse::<IpAddr>() {
+                Ok(ip) => ip,
+                Err(e) => {
+                    error!("actor: invalid ip string: {e}");
+                    continue;
+                }
+            };
+            // endpoint yang belum siap atau sedang dihentikan dilepas dari pembagian traffic
+            if !is_routable(endpoint) {
+                debug!("actor: draining unready pod endpoint {ip} of {namespace}/{servicename}");
+                continue;
+            }
+            let Some(nodename) = &endpoint.node_name else {
+                warn!("actor: missing nodename for pod endpoint of {ip}");
+                continue;
+            };
+            let endpoints = endpoints_by_nodename.entry(nodename.clone()).or_default();
+            // endpoint yang sama dapat muncul sementara pada dua slice ketika slice diseimbangkan ulang
+            if endpoints.iter().any(|existing| existing.ip == ip) {
+                continue;
             }
-            Ok(())
+            let pod = endpoint
+                .target_ref
+                .as_ref()
+                .filter(|target| target.kind.as_deref() == Some("Pod"))
+                .and_then(|target| target.name.clone());
+            endpoints.push(Endpoint {
+                ip,
+                pod,
+                zone: endpoint.zone.clone(),
+            });
         }
     }
+
+    // urutan endpoint dibuat tetap agar perubahan dapat dibandingkan antar event
+    for endpoints in endpoints_by_nodename.values_mut() {
+        endpoints.sort_by_key(|endpoint| endpoint.ip);
+    }
+
+    Some(Service {
+        namespace,
+        name: servicename,
+        ports,
+        endpoints_by_nodename,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn service(ports: serde_json::Value) -> KubernetesService {
+        serde_json::from_value(json!({
+            "metadata": { "namespace": "default", "name": "web" },
+            "spec": { "type": "NodePort", "ports": ports }
+        }))
+        .unwrap()
+    }
+
+    fn slice(name: &str, ports: serde_json::Value, endpoints: serde_json::Value) -> EndpointSlice {
+        serde_json::from_value(json!({
+            "metadata": {
+                "namespace": "default",
+                "name": name,
+                "labels": { SERVICE_NAME_LABEL: "web" }
+            },
+            "addressType": "IPv4",
+            "ports": ports,
+            "endpoints": endpoints
+        }))
+        .unwrap()
+    }
+
+    fn endpoint(endpoint: serde_json::Value) -> SliceEndpoint {
+        serde_json::from_value(endpoint).unwrap()
+    }
+
+    fn kubernetes_config() -> KubernetesConfig {
+        serde_json::from_value(json!({})).unwrap()
+    }
+
+    fn annotated(name: &str) -> KubernetesService {
+        serde_json::from_value(json!({
+            "metadata": {
+                "namespace": "default",
+                "name": name,
+                "annotations": { "latency-aware/enabled": "true" }
+            },
+            "spec": { "ports": [{ "port": 80, "targetPort": 8080, "nodePort": 30000 }] }
+        }))
+        .unwrap()
+    }
+
+    fn targetports(service: &Service) -> Vec<(i32, i32)> {
+        service
+            .ports
+            .iter()
+            .map(|port| (port.nodeport, port.targetport))
+            .collect()
+    }
+
+    #[test]
+    fn resolves_named_target_port_of_unnamed_service_port() {
+        let service = service(json!([
+            { "port": 80, "targetPort": "http", "nodePort": 30000 }
+        ]));
+        let slices = [slice(
+            "web-1",
+            json!([{ "name": "", "port": 8080, "protocol": "TCP" }]),
+            json!([]),
+        )];
+
+        let service = build_service(&service, slices.iter()).unwrap();
+
+        assert_eq!(targetports(&service), [(30000, 8080)]);
+    }
+
+    #[test]
+    fn resolves_named_target_port_by_service_port_name() {
+        let service = service(json!([
+            { "name": "web", "port": 80, "targetPort": "http", "nodePort": 30000 },
+            { "name": "metrics", "port": 9090, "targetPort": "prom", "nodePort": 30001 }
+        ]));
+        let slices = [slice(
+            "web-1",
+            json!([
+                { "name": "metrics", "port": 9100, "protocol": "TCP" },
+                { "name": "web", "port": 8080, "protocol": "TCP" }
+            ]),
+            json!([]),
+        )];
+
+        let service = build_service(&service, slices.iter()).unwrap();
+
+        assert_eq!(targetports(&service), [(30000, 8080), (30001, 9100)]);
+    }
+
+    #[test]
+    fn skips_unresolved_named_target_port() {
+        let service = service(json!([
+            { "name": "web", "port": 80, "targetPort": "http", "nodePort": 30000 },
+            { "name": "dns", "port": 53, "targetPort": 5353, "protocol": "UDP", "nodePort": 30053 }
+        ]));
+
+        let service = build_service(&service, [].iter()).unwrap();
+
+        // port Service tidak dipakai sebagai tujuan DNAT untuk target port bernama
+        assert_eq!(targetports(&service), [(30053, 5353)]);
+    }
+
+    #[test]
+    fn routes_ready_serving_endpoints_only() {
+        let routable = |conditions: serde_json::Value| {
+            is_routable(&endpoint(json!({
+                "addresses": ["10.0.0.1"],
+                "conditions": conditions
+            })))
+        };
+
+        assert!(is_routable(&endpoint(json!({ "addresses": ["10.0.0.1"] }))));
+        assert!(routable(json!({})));
+        assert!(routable(json!({ "ready": true, "serving": true })));
+        assert!(!routable(json!({ "ready": false })));
+        assert!(!routable(json!({ "ready": true, "serving": false })));
+        // pod yang sedang dihentikan masih serving namun tidak menerima koneksi baru
+        assert!(!routable(
+            json!({ "ready": false, "serving": true, "terminating": true })
+        ));
+    }
+
+    #[test]
+    fn merges_endpoints_of_every_slice() {
+        let service = service(json!([
+            { "port": 80, "targetPort": 8080, "nodePort": 30000 }
+        ]));
+        let ports = json!([{ "port": 8080 }]);
+        let slices = [
+            slice(
+                "web-1",
+                ports.clone(),
+                json!([
+                    {
+                        "addresses": ["10.0.0.3"],
+                        "nodeName": "node-b",
+                        "zone": "zone-b",
+                        "targetRef": { "kind": "Pod", "name": "web-3" }
+                    },
+                    { "addresses": ["10.0.0.1"], "nodeName": "node-a", "zone": "zone-a" },
+                    {
+                        "addresses": ["10.0.0.9"],
+                        "nodeName": "node-a",
+                        "conditions": { "ready": false }
+                    }
+                ]),
+            ),
+            // 10.0.0.3 muncul sementara pada dua slice selama slice diseimbangkan ulang
+            slice(
+                "web-2",
+                ports,
+                json!([
+                    { "addresses": ["10.0.0.3"], "nodeName": "node-b", "zone": "zone-b" },
+                    { "addresses": ["10.0.0.2"], "nodeName": "node-b", "zone": "zone-b" },
+                    { "addresses": ["10.0.0.4"] }
+                ]),
+            ),
+        ];
+
+        let service = build_service(&service, slices.iter()).unwrap();
+
+        let endpoint = |ip: &str, pod: Option<&str>, zone: &str| Endpoint {
+            ip: ip.parse().unwrap(),
+            pod: pod.map(str::to_string),
+            zone: Some(zone.to_string()),
+        };
+        assert_eq!(
+            service.endpoints_by_nodename,
+            HashMap::from([
+                (
+                    "node-a".to_string(),
+                    vec![endpoint("10.0.0.1", None, "zone-a")]
+                ),
+                (
+                    "node-b".to_string(),
+                    vec![
+                        endpoint("10.0.0.2", None, "zone-b"),
+                        endpoint("10.0.0.3", Some("web-3"), "zone-b"),
+                    ]
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn relist_drops_objects_missing_from_the_new_list() {
+        let config = kubernetes_config();
+        let scope = Some("default".to_string());
+        let slice = |name: &str, ip: &str| {
+            slice(
+                name,
+                json!([{ "port": 8080 }]),
+                json!([{ "addresses": [ip], "nodeName": "node-a" }]),
+            )
+        };
+        let mut cache = Cache::default();
+        cache.apply_service(&config, &scope, watcher::Event::Apply(annotated("web")));
+        cache.apply_service(&config, &scope, watcher::Event::Apply(annotated("api")));
+        cache.apply_slice(&scope, watcher::Event::Apply(slice("web-1", "10.0.0.1")));
+        cache.apply_slice(&scope, watcher::Event::Apply(slice("web-2", "10.0.0.2")));
+
+        // watcher melakukan list ulang, Service default/api dan slice web-2 telah dihapus
+        cache.apply_service(&config, &scope, watcher::Event::Init);
+        cache.apply_service(&config, &scope, watcher::Event::InitApply(annotated("web")));
+        let departed = cache.apply_service(&config, &scope, watcher::Event::InitDone);
+        assert_eq!(departed, ["default/api"]);
+        assert!(cache.desired("default/api").is_none());
+
+        cache.apply_slice(&scope, watcher::Event::Init);
+        cache.apply_slice(
+            &scope,
+            watcher::Event::InitApply(slice("web-1", "10.0.0.1")),
+        );
+        let departed = cache.apply_slice(&scope, watcher::Event::InitDone);
+        assert_eq!(departed, ["default/web"]);
+
+        let web = cache.desired("default/web").unwrap();
+        assert_eq!(
+            web.endpoints_by_nodename["node-a"]
+                .iter()
+                .map(|endpoint| endpoint.ip.to_string())
+                .collect::<Vec<_>>(),
+            ["10.0.0.1"]
+        );
+    }
+
+    #[test]
+    fn relist_keeps_objects_of_other_namespaces() {
+        let config = kubernetes_config();
+        let mut cache = Cache::default();
+        cache.apply_service(
+            &config,
+            &Some("default".to_string()),
+            watcher::Event::Apply(annotated("web")),
+        );
+
+        let other = Some("shop".to_string());
+        cache.apply_service(&config, &other, watcher::Event::Init);
+        let departed = cache.apply_service(&config, &other, watcher::Event::InitDone);
+
+        assert!(departed.is_empty());
+        assert!(cache.desired("default/web").is_some());
+    }
 }
@@ -0,0 +1,41 @@
+use std::net::SocketAddr;
+
+use anyhow::bail;
+use tokio::time::Duration;
+use tonic::transport::Endpoint;
+use tonic_health::pb::{
+    HealthCheckRequest, health_check_response::ServingStatus, health_client::HealthClient,
+};
+
+/// Pemeriksaan melalui RPC standar `grpc.health.v1.Health/Check`
+#[derive(Debug)]
+pub struct GrpcCheck {
+    /// Nama service yang diperiksa, kosong berarti kesehatan server secara keseluruhan
+    pub service: String,
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+}
+
+impl GrpcCheck {
+    /// Memanggil RPC Check pada endpoint, status selain `SERVING` dianggap sebagai kegagalan
+    pub async fn probe(&self, address: SocketAddr) -> anyhow::Result<()> {
+        // koneksi baru dibuka pada setiap probe sehingga handshake HTTP/2 ikut terukur,
+        // sama seperti probe HTTP dengan pool koneksi kosong
+        let channel = Endpoint::from_shared(format!("http://{address}"))?
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout)
+            .connect()
+            .await?;
+        let response = HealthClient::new(channel)
+            .check(HealthCheckRequest {
+                service: self.service.clone(),
+            })
+            .await?;
+
+        let status = response.into_inner().status();
+        if status != ServingStatus::Serving {
+            bail!("grpc health status {}", status.as_str_name());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use std::net::SocketAddr;
+
+use anyhow::{anyhow, bail};
+use regex::Regex;
+use reqwest::{
+    Client, Method,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+
+use crate::config::{BodyMatch, HttpCheckConfig};
+
+/// Pemeriksaan HTTP sebuah Service yang telah divalidasi dari konfigurasi
+#[derive(Debug)]
+pub struct HttpCheck {
+    method: Method,
+    path: String,
+    headers: HeaderMap,
+    body: Option<String>,
+    accepted_statuses: Vec<u16>,
+    body_match: Option<BodyMatcher>,
+}
+
+#[derive(Debug)]
+enum BodyMatcher {
+    Contains(String),
+    Regex(Regex),
+}
+
+impl HttpCheck {
+    pub fn from_config(config: &HttpCheckConfig) -> anyhow::Result<Self> {
+        if !config.path.starts_with('/') {
+            bail!("probe path {} must start with /", config.path);
+        }
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+        let body_match = match &config.body_match {
+            Some(BodyMatch::Contains(substring)) => Some(BodyMatcher::Contains(substring.clone())),
+            Some(BodyMatch::Regex(pattern)) => Some(BodyMatcher::Regex(Regex::new(pattern)?)),
+            None => None,
+        };
+
+        Ok(Self {
+            method: Method::from_bytes(config.method.as_bytes())?,
+            path: config.path.clone(),
+            headers,
+            body: config.body.clone(),
+            accepted_statuses: config.accepted_statuses.clone(),
+            body_match,
+        })
+    }
+
+    /// Mengirim request pemeriksaan kepada endpoint, response dengan status yang tidak
+    /// diterima atau body yang tidak sesuai dianggap sebagai kegagalan
+    pub async fn probe(&self, client: &Client, address: SocketAddr) -> anyhow::Result<()> {
+        let mut request = client
+            .request(
+                self.method.clone(),
+                format!("http://{address}{}", self.path),
+            )
+            .headers(self.headers.clone());
+        if let Some(body) = &self.body {
+            request = request.body(body.clone());
+        }
+        let response = request.send().await?;
+
+        let status = response.status();
+        let accepted = if self.accepted_statuses.is_empty() {
+            status.is_success()
+        } else {
+            self.accepted_statuses.contains(&status.as_u16())
+        };
+        if !accepted {
+            bail!("unexpected status {status}");
+        }
+
+        // body hanya dibaca ketika perlu dicocokkan
+        let Some(body_match) = &self.body_match else {
+            return Ok(());
+        };
+        let body = response.text().await?;
+        let matched = match body_match {
+            BodyMatcher::Contains(substring) => body.contains(substring.as_str()),
+            BodyMatcher::Regex(regex) => regex.is_match(&body),
+        };
+        if !matched {
+            return Err(anyhow!("response body does not match {body_match:?}"));
+        }
+        Ok(())
+    }
+}
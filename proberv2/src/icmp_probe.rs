@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+};
+
+use anyhow::bail;
+use futures::future::join_all;
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::{
+    net::UdpSocket,
+    sync::broadcast,
+    time::{Duration, Instant, interval, timeout_at},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::{
+    actor::{Event, EwmaDatapoint, WorkerNode, try_next_event},
+    config::Config,
+    metrics,
+};
+
+const ICMPV4_ECHO_REQUEST: u8 = 8;
+const ICMPV4_ECHO_REPLY: u8 = 0;
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+const ICMPV6_ECHO_REPLY: u8 = 129;
+
+/// Mengukur round-trip time jaringan menuju setiap worker node melalui ICMP echo,
+/// terpisah dari latency aplikasi yang diukur oleh probe latency
+pub async fn probe_icmp(
+    config: Config,
+    tx: broadcast::Sender<Event>,
+    token: CancellationToken,
+) -> anyhow::Result<()> {
+    let mut ticker = interval(Duration::from_secs(config.probe.icmp_interval));
+    let timeout = Duration::from_millis(config.probe.icmp_timeout_ms);
+    let mut nodes = HashMap::<String, WorkerNode>::new();
+    let mut datapoint_by_nodename = HashMap::<String, f64>::new();
+    let mut sequence = 0u16;
+
+    let mut rx = tx.subscribe();
+    'main: loop {
+        // hentikan main loop ketika program shutdown
+        if token.is_cancelled() {
+            info!("actor: exiting probe_icmp task");
+            return Ok(());
+        }
+
+        while let Some(event) = try_next_event(&mut rx) {
+            match event {
+                Event::NodeJoined(node) => {
+                    nodes.insert(node.name.clone(), node);
+                }
+                // RTT menuju IP lama tidak lagi relevan
+                Event::NodeUpdated(node) => {
+                    datapoint_by_nodename.remove(&node.name);
+                    nodes.insert(node.name.clone(), node);
+                }
+                Event::NodeLeft(node) => {
+                    datapoint_by_nodename.remove(&node.name);
+                    nodes.remove(&node.name);
+                }
+                _ => {}
+            }
+        }
+
+        sequence = sequence.wrapping_add(1);
+        let rtts = join_all(
+            nodes
+                .values()
+                .map(|worker| async move { (worker, ping(worker.ip, sequence, timeout).await) }),
+        )
+        .await;
+
+        for (worker, rtt) in rtts {
+            let rtt_ms = match rtt {
+                Ok(rtt) => rtt.as_secs_f64() * 1000.0,
+                Err(e) => {
+                    warn!("actor: failed to ping node {}: {e}", worker.name);
+                    continue;
+                }
+            };
+            debug!("actor: icmp rtt of {} takes {rtt_ms} ms", worker.name);
+            metrics::set_raw_rtt_ms(&worker.name, rtt_ms);
+
+            let alpha = config.alpha.ewma_rtt;
+            let datapoint = match datapoint_by_nodename.get(&worker.name) {
+                Some(datapoint) => alpha * rtt_ms + (1.0 - alpha) * *datapoint,
+                None => rtt_ms,
+            };
+            datapoint_by_nodename.insert(worker.name.clone(), datapoint);
+            metrics::set_ewma_rtt(&worker.name, datapoint);
+
+            if let Err(e) = tx.send(Event::EwmaCalculated(
+                worker.name.clone(),
+                EwmaDatapoint::Rtt(datapoint),
+            )) {
+                info!("actor: icmp probe exiting: {e}");
+                break 'main;
+            };
+        }
+
+        ticker.tick().await;
+    }
+
+    Ok(())
+}
+
+/// Mengirim satu ICMP echo request dan menunggu balasannya, mengembalikan round-trip time.
+///
+/// Socket datagram ICMP (tanpa hak akses khusus selama `net.ipv4.ping_group_range`
+/// mengizinkan) dicoba terlebih dahulu, kemudian raw socket yang membutuhkan CAP_NET_RAW
+pub async fn ping(ip: IpAddr, sequence: u16, timeout: Duration) -> anyhow::Result<Duration> {
+    let (domain, protocol) = match ip {
+        IpAddr::V4(_) => (Domain::IPV4, Protocol::ICMPV4),
+        IpAddr::V6(_) => (Domain::IPV6, Protocol::ICMPV6),
+    };
+    let (socket, raw) = match Socket::new(domain, Type::DGRAM, Some(protocol)) {
+        Ok(socket) => (socket, false),
+        Err(_) => (Socket::new(domain, Type::RAW, Some(protocol))?, true),
+    };
+    socket.set_nonblocking(true)?;
+    let socket = UdpSocket::from_std(std::net::UdpSocket::from(socket))?;
+
+    // kernel mengganti identifier milik socket datagram dengan nomor port lokal
+    let identifier = std::process::id() as u16;
+    let request = echo_request(ip.is_ipv6(), identifier, sequence);
+    let target = SocketAddr::new(ip, 0);
+
+    let deadline = Instant::now() + timeout;
+    let start = Instant::now();
+    socket.send_to(&request, target).await?;
+
+    let mut buffer = [0u8; 1500];
+    loop {
+        let Ok(received) = timeout_at(deadline, socket.recv_from(&mut buffer)).await else {
+            bail!("no echo reply within {timeout:?}");
+        };
+        let (len, from) = received?;
+        // raw socket menerima seluruh paket ICMP termasuk milik proses lain
+        if from.ip() != ip {
+            continue;
+        }
+        let packet = match (raw, ip) {
+            // raw socket IPv4 menyertakan header IP pada paket yang diterima
+            (true, IpAddr::V4(_)) => strip_ipv4_header(&buffer[..len]),
+            _ => Some(&buffer[..len]),
+        };
+        if packet.is_some_and(|packet| {
+            is_echo_reply(packet, ip.is_ipv6(), raw.then_some(identifier), sequence)
+        }) {
+            return Ok(start.elapsed());
+        }
+    }
+}
+
+/// Membangun paket ICMP echo request beserta checksum-nya
+pub fn echo_request(ipv6: bool, identifier: u16, sequence: u16) -> Vec<u8> {
+    let kind = if ipv6 {
+        ICMPV6_ECHO_REQUEST
+    } else {
+        ICMPV4_ECHO_REQUEST
+    };
+    let mut packet = vec![kind, 0, 0, 0];
+    packet.extend_from_slice(&identifier.to_be_bytes());
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(b"proberv2");
+
+    // checksum ICMPv6 mencakup pseudo header IPv6 sehingga selalu dihitung oleh kernel
+    if !ipv6 {
+        let checksum = checksum(&packet);
+        packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+    packet
+}
+
+/// Memeriksa apakah paket ICMP merupakan balasan echo dengan sequence yang sesuai,
+/// identifier hanya diperiksa ketika diberikan (raw socket)
+pub fn is_echo_reply(packet: &[u8], ipv6: bool, identifier: Option<u16>, sequence: u16) -> bool {
+    let reply = if ipv6 {
+        ICMPV6_ECHO_REPLY
+    } else {
+        ICMPV4_ECHO_REPLY
+    };
+    packet.len() >= 8
+        && packet[0] == reply
+        && identifier.is_none_or(|identifier| packet[4..6] == identifier.to_be_bytes())
+        && packet[6..8] == sequence.to_be_bytes()
+}
+
+fn strip_ipv4_header(packet: &[u8]) -> Option<&[u8]> {
+    let header_len = (*packet.first()? & 0x0f) as usize * 4;
+    packet.get(header_len..)
+}
+
+/// Internet checksum (RFC 1071)
+fn checksum(packet: &[u8]) -> u16 {
+    let mut sum = packet
+        .chunks(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], *chunk.get(1).unwrap_or(&0)]) as u32)
+        .sum::<u32>();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
@@ -1,9 +1,19 @@
-use std::{collections::HashMap, net::Ipv4Addr};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use futures::future::join_all;
 
-use crate::config::Config;
+use crate::config::{Config, HttpProbeConfig};
+use crate::latency_statistic::summarize;
 use crate::metrics;
+use crate::outlier::{OutlierDetector, Transition};
+use crate::probe_check::ProbeCheck;
 
-use super::actor::{Event, EwmaDatapoint};
+use super::actor::{Event, EwmaDatapoint, Service, try_next_event};
 use tokio::task;
 use tokio::{
     sync::broadcast,
@@ -18,8 +28,25 @@ pub async fn probe_latency(
     token: CancellationToken,
 ) -> anyhow::Result<()> {
     let mut ticker = interval(Duration::from_secs(config.probe.latency_interval));
-    let mut endpoints_by_nodename = HashMap::<String, Vec<Ipv4Addr>>::new();
-    let mut datapoint_by_nodename = HashMap::<String, f64>::new();
+    // satu klien digunakan bersama oleh seluruh probe agar setiap request memiliki batas waktu
+    let client = http_client(&config.probe.http)?;
+    // pemeriksaan divalidasi sekali saat startup sehingga konfigurasi yang salah langsung terlihat
+    let default_check = Arc::new(ProbeCheck::from_config(
+        &config.probe.default_check,
+        &config.probe.http,
+    )?);
+    let mut check_by_service = HashMap::new();
+    for (servicekey, check) in &config.probe.service_checks {
+        let check = ProbeCheck::from_config(check, &config.probe.http)
+            .map_err(|e| anyhow!("invalid probe check of service {servicekey}: {e}"))?;
+        check_by_service.insert(servicekey.clone(), Arc::new(check));
+    }
+    let mut service_by_key = HashMap::<String, Service>::new();
+    // (Service Key, Node Name) -> skor EWMA latency
+    let mut datapoint_by_target = HashMap::<(String, String), f64>::new();
+    // (Service Key, IP endpoint) -> skor EWMA latency setiap pod
+    let mut datapoint_by_endpoint = HashMap::<(String, IpAddr), f64>::new();
+    let mut outliers = OutlierDetector::new(config.outlier.clone());
 
     let mut rx = tx.subscribe();
     'main: loop {
@@ -30,41 +57,89 @@ pub async fn probe_latency(
         }
 
         // mencoba membaca event perubahan Service pada channel
-        while let Ok(event) = rx.try_recv() {
-            if let Event::ServiceChanged(service) = event {
-                endpoints_by_nodename = service.endpoints_by_nodename;
-            };
+        while let Some(event) = try_next_event(&mut rx) {
+            match event {
+                Event::ServiceChanged(service) => {
+                    let servicekey = service.key();
+                    datapoint_by_target.retain(|(key, nodename), _| {
+                        *key != servicekey || service.endpoints_by_nodename.contains_key(nodename)
+                    });
+                    outliers.retain(|(key, nodename)| {
+                        *key != servicekey || service.endpoints_by_nodename.contains_key(nodename)
+                    });
+                    datapoint_by_endpoint
+                        .retain(|(key, ip), _| *key != servicekey || service.has_endpoint(*ip));
+                    service_by_key.insert(servicekey, service);
+                }
+                Event::ServiceRemoved(service) => {
+                    let servicekey = service.key();
+                    datapoint_by_target.retain(|(key, _), _| *key != servicekey);
+                    outliers.retain(|(key, _)| *key != servicekey);
+                    datapoint_by_endpoint.retain(|(key, _), _| *key != servicekey);
+                    service_by_key.remove(&servicekey);
+                }
+                // berhenti melakukan probe pada node yang telah keluar dari cluster
+                Event::NodeLeft(node) => {
+                    for service in service_by_key.values_mut() {
+                        service.endpoints_by_nodename.remove(&node.name);
+                    }
+                    datapoint_by_target.retain(|(_, nodename), _| *nodename != node.name);
+                    outliers.retain(|(_, nodename)| *nodename != node.name);
+                    datapoint_by_endpoint.retain(|(key, ip), _| {
+                        service_by_key
+                            .get(key)
+                            .is_some_and(|service| service.has_endpoint(*ip))
+                    });
+                }
+                _ => {}
+            }
         }
 
         let mut handles = Vec::new();
-        endpoints_by_nodename
-            .iter()
-            // melakukan request laman / pada salah satu pod aplikasi yang berjalan pada setiap node
-            .for_each(|(nodename, endpoints)| {
+        // melakukan probe pada setiap pod aplikasi yang berjalan pada setiap node
+        // untuk setiap Service secara terpisah
+        for service in service_by_key.values() {
+            let Some(port) = service.probe_port() else {
+                debug!("actor: service {} has no tcp port to probe", service.key());
+                continue;
+            };
+            let check = check_by_service
+                .get(&service.key())
+                .unwrap_or(&default_check);
+            for (nodename, endpoints) in &service.endpoints_by_nodename {
+                let servicekey = service.key();
+                let check = check.clone();
                 let nodename = nodename.clone();
                 let endpoints = endpoints.clone();
+                let targetport = port.targetport;
+                let client = client.clone();
+
+                // jumlah sampel berlaku untuk seluruh node, namun setiap endpoint tetap
+                // memperoleh minimal satu sampel agar EWMA setiap pod selalu diperbarui
+                let samples = config.probe.latency_samples.max(endpoints.len()).max(1);
 
                 handles.push(task::spawn(async move {
-                    let mut response_time_ms: Option<u128> = None;
-                    for endpoint in endpoints {
-                        // inisialisasi waktu sebelum request laman dimulai
-                        let now = Instant::now();
-                        // melakukan request laman /
-                        if (reqwest::get(format!(
-                            "http://{}:{}",
-                            endpoint, config.kubernetes.target_port
-                        ))
-                        .await)
-                            .is_ok()
-                        {
-                            // menghitung waktu respon semenjak waktu inisialisasi
-                            response_time_ms = Some(now.elapsed().as_millis());
-                            break;
-                        };
-                    }
-                    (nodename, response_time_ms)
+                    // sampel dibagi bergantian kepada endpoint node dan setiap endpoint diukur
+                    // secara terpisah sehingga pod yang lambat tidak tertutupi oleh pod lain
+                    let response_times_ms =
+                        join_all(endpoints.iter().enumerate().map(|(idx, endpoint)| {
+                            let address = SocketAddr::new(endpoint.ip, targetport as u16);
+                            let (client, check) = (&client, &check);
+                            let samples = samples / endpoints.len()
+                                + usize::from(idx < samples % endpoints.len());
+                            async move {
+                                let mut samples_ms = Vec::with_capacity(samples);
+                                for _ in 0..samples {
+                                    samples_ms.push(sample_latency(client, check, address).await);
+                                }
+                                (endpoint.ip, samples_ms)
+                            }
+                        }))
+                        .await;
+                    ((servicekey, nodename), response_times_ms)
                 }));
-            });
+            }
+        }
 
         // membaca semua hasil pengukuran waktu respon pada proses sebelumnya
         let mut response_times = Vec::new();
@@ -81,23 +156,87 @@ pub async fn probe_latency(
 
         // menghitung skor EWMA untuk setiap hasil waktu respon dan mengirim kumpulan skor tersebut
         // melalui channel sebagai event EwmaCalculated
-        for (nodename, response_time) in response_times {
-            let Some(elapsed_ms) = response_time else {
-                warn!("actor: failed to probe latency for any endpoints available @ {nodename}");
-                continue;
+        for (target, samples_by_endpoint) in response_times {
+            let (servicekey, nodename) = &target;
+
+            // latency setiap pod dikirim terpisah untuk membagi porsi node kepada pod-podnya
+            for (ip, samples) in &samples_by_endpoint {
+                let elapsed_ms =
+                    summarize_samples(&config, samples).unwrap_or(config.probe.failure_penalty_ms);
+                let datapoint = match datapoint_by_endpoint.get(&(servicekey.clone(), *ip)) {
+                    Some(datapoint) => {
+                        config.alpha.ewma_latency * elapsed_ms
+                            + (1.0 - config.alpha.ewma_latency) * *datapoint
+                    }
+                    None => elapsed_ms,
+                };
+                datapoint_by_endpoint.insert((servicekey.clone(), *ip), datapoint);
+                debug!("actor: latency probe of {servicekey} @ {ip} scores {datapoint} ms");
+
+                if let Err(e) = tx.send(Event::EndpointEwmaCalculated(
+                    servicekey.clone(),
+                    *ip,
+                    EwmaDatapoint::Latency(datapoint),
+                )) {
+                    info!("actor: latency probe exiting: {e}");
+                    break 'main;
+                };
+            }
+
+            let samples: Vec<Option<f64>> = samples_by_endpoint
+                .into_iter()
+                .flat_map(|(_, samples)| samples)
+                .collect();
+            for elapsed_ms in samples.iter().flatten() {
+                metrics::observe_latency_sample_ms(nodename, servicekey, *elapsed_ms);
+            }
+            let response_time = summarize_samples(&config, &samples);
+
+            // node yang terus gagal merespon dikeluarkan dari pembagian traffic Service
+            // hingga kembali merespon probe setelah masa ejection berakhir
+            let event = match outliers.record(&target, response_time.is_some(), Instant::now()) {
+                Some(Transition::Ejected(duration)) => {
+                    warn!(
+                        "actor: ejecting node {nodename} from service {servicekey} for {duration:?} after failed latency probes"
+                    );
+                    metrics::set_node_ejected(nodename, servicekey, true);
+                    Some(Event::NodeEjected(servicekey.clone(), nodename.clone()))
+                }
+                Some(Transition::Readmitted) => {
+                    info!("actor: readmitting node {nodename} to service {servicekey}");
+                    metrics::set_node_ejected(nodename, servicekey, false);
+                    Some(Event::NodeReadmitted(servicekey.clone(), nodename.clone()))
+                }
+                None => None,
+            };
+            if let Some(event) = event
+                && let Err(e) = tx.send(event)
+            {
+                info!("actor: latency probe exiting: {e}");
+                break 'main;
+            }
+
+            // kegagalan dicatat sebagai latency penalti agar skor node ikut memburuk
+            let elapsed_ms = match response_time {
+                Some(elapsed_ms) => elapsed_ms,
+                None => {
+                    warn!(
+                        "actor: failed to probe latency of {servicekey} for any endpoints available @ {nodename}, recording {} ms penalty",
+                        config.probe.failure_penalty_ms
+                    );
+                    config.probe.failure_penalty_ms
+                }
             };
 
             debug!(
-                "actor: latency probe of {} takes {} ms",
-                nodename, elapsed_ms
+                "actor: latency probe of {} @ {} takes {} ms",
+                servicekey, nodename, elapsed_ms
             );
 
-            let elapsed_ms = elapsed_ms as f64;
-
             // menulis metrik latency mentah (pre-EWMA) ke Prometheus
-            metrics::set_raw_latency_ms(&nodename, elapsed_ms);
+            metrics::set_raw_latency_ms(nodename, servicekey, elapsed_ms);
 
-            let datapoint = match datapoint_by_nodename.get(&nodename) {
+            let datapoint = match datapoint_by_target.get(&target) {
                 // kalkulasi skor EWMA ketika terdapat skor pada titik sebelumnya
                 Some(datapoint) => {
                     config.alpha.ewma_latency * elapsed_ms
@@ -108,21 +247,22 @@ pub async fn probe_latency(
                 None => elapsed_ms,
             };
 
-            // menyimpan nilai skor EWMA per node untuk digunakan pada perhitungan skor selanjutnya
-            datapoint_by_nodename.insert(nodename.clone(), datapoint);
-
             // menulis metrik EWMA latency ke Prometheus
-            metrics::set_ewma_latency(&nodename, datapoint);
+            metrics::set_ewma_latency(nodename, servicekey, datapoint);
 
-            // mengirim hasil skor EWMA untuk metrik waktu respon untuk
-            // setiap node sebagai event EwmaCalculated melalui channel
-            if let Err(e) = tx.send(Event::EwmaCalculated(
+            // mengirim hasil skor EWMA untuk metrik waktu respon untuk setiap
+            // pasangan Service dan node sebagai event ServiceEwmaCalculated melalui channel
+            if let Err(e) = tx.send(Event::ServiceEwmaCalculated(
+                servicekey.clone(),
                 nodename.clone(),
                 EwmaDatapoint::Latency(datapoint),
             )) {
                 info!("actor: latency probe exiting: {e}");
                 break 'main;
             };
+
+            // menyimpan nilai skor EWMA per Service dan node untuk digunakan pada perhitungan skor selanjutnya
+            datapoint_by_target.insert(target, datapoint);
         }
 
         // memberhentikan sementara eksekusi loop selanjutnya
@@ -132,3 +272,48 @@ pub async fn probe_latency(
 
     Ok(())
 }
+
+/// Membangun klien HTTP probe sesuai konfigurasi
+fn http_client(config: &HttpProbeConfig) -> anyhow::Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_millis(config.timeout_ms))
+        .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .user_agent(&config.user_agent)
+        .build()?)
+}
+
+/// Mengambil satu sampel latency sebuah endpoint, `None` ketika pemeriksaan gagal
+async fn sample_latency(
+    client: &reqwest::Client,
+    check: &ProbeCheck,
+    address: SocketAddr,
+) -> Option<f64> {
+    let now = Instant::now();
+    // melakukan pemeriksaan Service sesuai mode probe
+    match check.probe(client, address).await {
+        // menghitung waktu respon semenjak waktu inisialisasi
+        Ok(()) => Some(now.elapsed().as_secs_f64() * 1000.0),
+        Err(e) => {
+            debug!("actor: probe {address} failed: {e}");
+            None
+        }
+    }
+}
+
+/// Meringkas sampel latency sesuai statistik pada konfigurasi. Hasil dianggap gagal hanya
+/// ketika seluruh sampel gagal, sampel gagal lainnya ikut diringkas sebagai latency penalti
+fn summarize_samples(config: &Config, samples: &[Option<f64>]) -> Option<f64> {
+    if samples.iter().all(Option::is_none) {
+        return None;
+    }
+    let samples: Vec<f64> = samples
+        .iter()
+        .map(|sample| sample.unwrap_or(config.probe.failure_penalty_ms))
+        .collect();
+    summarize(
+        &samples,
+        config.probe.latency_statistic,
+        config.probe.trim_fraction,
+    )
+}
@@ -0,0 +1,33 @@
+use crate::config::LatencyStatistic;
+
+/// Meringkas sampel latency dalam satu tick menjadi satu nilai sesuai statistik yang dipilih,
+/// `None` ketika tidak ada sampel
+pub fn summarize(samples: &[f64], statistic: LatencyStatistic, trim_fraction: f64) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    Some(match statistic {
+        LatencyStatistic::P50 => percentile(&sorted, 0.50),
+        LatencyStatistic::P90 => percentile(&sorted, 0.90),
+        LatencyStatistic::P99 => percentile(&sorted, 0.99),
+        LatencyStatistic::TrimmedMean => {
+            // sampel terkecil dan terbesar dibuang dalam jumlah yang sama
+            let trimmed = (sorted.len() as f64 * trim_fraction.clamp(0.0, 0.5)).floor() as usize;
+            let kept = &sorted[trimmed..sorted.len() - trimmed];
+            if kept.is_empty() {
+                percentile(&sorted, 0.50)
+            } else {
+                kept.iter().sum::<f64>() / kept.len() as f64
+            }
+        }
+    })
+}
+
+/// Persentil dengan metode nearest-rank pada sampel yang telah terurut
+fn percentile(sorted: &[f64], quantile: f64) -> f64 {
+    let rank = (quantile * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
@@ -1,9 +1,28 @@
 pub mod actor;
 pub mod config;
+pub mod cpu_source;
 pub mod cpu_usage_probe;
+pub mod dampening;
 pub mod endpoints_watch;
+pub mod grpc_probe;
+pub mod http_probe;
+pub mod icmp_probe;
 pub mod latency_probe;
+pub mod latency_statistic;
+pub mod memory_probe;
+pub mod memory_source;
 pub mod metrics;
+pub mod network_probe;
+pub mod network_source;
+pub mod nftables_backend;
+pub mod node_signal_probe;
 pub mod node_watch;
+pub mod outlier;
+pub mod pod_cpu_probe;
+pub mod pod_cpu_source;
+pub mod probe_check;
+pub mod scoring;
 pub mod setup_nftables;
+pub mod slot_allocator;
+pub mod teardown_nftables;
 pub mod update_nftables;
@@ -1,7 +1,10 @@
-use std::{collections::HashMap, env, path::Path, time::Duration};
+use std::{env, path::Path, sync::Arc, time::Duration};
 
 use axum::{Router, routing::get};
-use proberv2::{actor::Actor, config::Config, metrics, setup_nftables::setup_nftables};
+use proberv2::{
+    actor::Actor, config::Config, metrics, nftables_backend::NftCli, scoring::scoring_policy,
+    setup_nftables::setup_nftables,
+};
 use tokio::{
     fs,
     signal::unix::{self, SignalKind},
@@ -27,6 +30,9 @@ async fn main() -> anyhow::Result<()> {
     let node_name = env::var("NODENAME")?;
     config.kubernetes.node_name = node_name;
 
+    // parameter scoring yang tidak valid ditolak sebelum proses apapun berjalan
+    scoring_policy(&config)?;
+
     // inisialisasi metrics registry
     metrics::init();
 
@@ -58,12 +64,9 @@ async fn main() -> anyhow::Result<()> {
         });
     }
 
-    let mut actor = Actor {
-        config: config.clone(),
-        datapoint_by_nodename: HashMap::new(),
-        service_by_nodeport: HashMap::new(),
-    };
-    setup_nftables(&config).await?;
+    let backend = Arc::new(NftCli);
+    let mut actor = Actor::new(config.clone(), backend.clone());
+    setup_nftables(&config, backend.as_ref()).await?;
 
     tokio::spawn(async move { actor.dispatch(child_token).await });
 
@@ -0,0 +1,30 @@
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    actor::{Event, EwmaDatapoint},
+    config::Config,
+    memory_source::memory_source,
+    metrics,
+    node_signal_probe::{NodeSignal, probe_node_signal},
+};
+
+/// Mengukur tekanan memori setiap worker node agar node yang mendekati OOM
+/// tidak terus menerima traffic selama CPU-nya masih longgar
+pub async fn probe_memory(
+    config: Config,
+    tx: broadcast::Sender<Event>,
+    token: CancellationToken,
+) -> anyhow::Result<()> {
+    let signal = NodeSignal {
+        name: "memory pressure",
+        interval: config.probe.memory_interval,
+        alpha: config.alpha.ewma_memory,
+        source: memory_source(&config)?,
+        read: |source, nodes| source.memory_pressure(nodes),
+        set_raw: metrics::set_raw_memory_pressure,
+        set_ewma: metrics::set_ewma_memory,
+        datapoint: EwmaDatapoint::Memory,
+    };
+    probe_node_signal(signal, tx, token).await
+}
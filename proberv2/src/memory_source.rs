@@ -0,0 +1,128 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{anyhow, bail};
+use futures::future::BoxFuture;
+
+use crate::{
+    actor::WorkerNode,
+    config::{Config, MemorySourceConfig},
+    cpu_source::Prometheus,
+};
+
+/// Sumber tekanan memori worker node
+pub trait MemorySource: Send + Sync {
+    /// Tekanan memori (0 hingga 1, semakin besar semakin mendekati OOM) setiap node yang
+    /// berhasil dibaca, Node Name -> tekanan. Node yang gagal dibaca tidak disertakan
+    fn memory_pressure<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>>;
+}
+
+/// `/proc/meminfo` milik node tempat prober berjalan, tekanan berupa porsi memori
+/// yang tidak tersedia (`1 - MemAvailable / MemTotal`)
+pub struct ProcMeminfo {
+    pub path: PathBuf,
+    pub node_name: String,
+}
+
+/// PSI `/proc/pressure/memory` milik node tempat prober berjalan, tekanan berupa
+/// porsi waktu `some avg10` ketika task tertahan menunggu memori
+pub struct ProcPressure {
+    pub path: PathBuf,
+    pub node_name: String,
+}
+
+impl MemorySource for Prometheus {
+    fn memory_pressure<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>> {
+        Box::pin(self.query_nodes(nodes))
+    }
+}
+
+impl MemorySource for ProcMeminfo {
+    fn memory_pressure<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>> {
+        Box::pin(async move {
+            if !nodes.iter().any(|worker| worker.name == self.node_name) {
+                return Ok(HashMap::new());
+            }
+            let meminfo = tokio::fs::read_to_string(&self.path).await?;
+            Ok(HashMap::from([(
+                self.node_name.clone(),
+                parse_meminfo(&meminfo)?,
+            )]))
+        })
+    }
+}
+
+impl MemorySource for ProcPressure {
+    fn memory_pressure<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>> {
+        Box::pin(async move {
+            if !nodes.iter().any(|worker| worker.name == self.node_name) {
+                return Ok(HashMap::new());
+            }
+            let pressure = tokio::fs::read_to_string(&self.path).await?;
+            Ok(HashMap::from([(
+                self.node_name.clone(),
+                parse_pressure(&pressure)?,
+            )]))
+        })
+    }
+}
+
+/// Sumber tekanan memori yang dipilih pada konfigurasi
+pub fn memory_source(config: &Config) -> anyhow::Result<Box<dyn MemorySource>> {
+    Ok(match &config.probe.memory_source {
+        MemorySourceConfig::Prometheus => Box::new(Prometheus {
+            client: prometheus_http_query::Client::try_from(config.prometheus.url.clone())?,
+            query: config.prometheus.memory_query.clone(),
+            join_label: config.prometheus.join_label.clone(),
+            join_by: config.prometheus.join_by,
+        }),
+        MemorySourceConfig::ProcMeminfo { path } => Box::new(ProcMeminfo {
+            path: path.into(),
+            node_name: config.kubernetes.node_name.clone(),
+        }),
+        MemorySourceConfig::ProcPressure { path } => Box::new(ProcPressure {
+            path: path.into(),
+            node_name: config.kubernetes.node_name.clone(),
+        }),
+    })
+}
+
+/// Porsi memori yang tidak tersedia dari isi `/proc/meminfo`
+pub fn parse_meminfo(meminfo: &str) -> anyhow::Result<f64> {
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.split_whitespace().next()?.parse::<f64>().ok())
+            .ok_or_else(|| anyhow!("{name} not found in meminfo"))
+    };
+    let total = field("MemTotal")?;
+    if total <= 0.0 {
+        bail!("MemTotal is zero");
+    }
+    Ok((1.0 - field("MemAvailable")? / total).clamp(0.0, 1.0))
+}
+
+/// Porsi waktu `some avg10` (0 hingga 1) dari isi `/proc/pressure/memory`
+pub fn parse_pressure(pressure: &str) -> anyhow::Result<f64> {
+    let avg10 = pressure
+        .lines()
+        .find_map(|line| line.strip_prefix("some "))
+        .and_then(|line| {
+            line.split_whitespace()
+                .find_map(|field| field.strip_prefix("avg10="))
+        })
+        .ok_or_else(|| anyhow!("some avg10 not found in memory pressure"))?;
+    Ok((avg10.parse::<f64>()? / 100.0).clamp(0.0, 1.0))
+}
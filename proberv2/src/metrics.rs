@@ -1,5 +1,7 @@
 use lazy_static::lazy_static;
-use prometheus::{Encoder, GaugeVec, Opts, Registry, TextEncoder};
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
 
 lazy_static! {
     static ref REGISTRY: Registry = Registry::new();
@@ -11,20 +13,59 @@ lazy_static! {
     ).unwrap();
     static ref EWMA_LATENCY: GaugeVec = GaugeVec::new(
         Opts::new("proberv2_ewma_latency_score_ms", "EWMA-smoothed response time in ms per node"),
-        &["node"],
+        &["node", "service"],
     ).unwrap();
     static ref RAW_CPU: GaugeVec = GaugeVec::new(
         Opts::new("proberv2_raw_cpu_usage", "Raw (pre-EWMA) CPU usage fraction per node"),
         &["node"],
     ).unwrap();
-    static ref RAW_LATENCY: GaugeVec = GaugeVec::new(
-        Opts::new("proberv2_raw_latency_ms", "Raw measured response time in ms per node"),
+    static ref EWMA_RTT: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_ewma_rtt_ms", "EWMA-smoothed ICMP round-trip time in ms per node"),
+        &["node"],
+    ).unwrap();
+    static ref RAW_RTT: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_raw_rtt_ms", "Raw measured ICMP round-trip time in ms per node"),
+        &["node"],
+    ).unwrap();
+    static ref EWMA_MEMORY: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_ewma_memory_pressure", "EWMA-smoothed memory pressure fraction per node"),
         &["node"],
     ).unwrap();
+    static ref RAW_MEMORY: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_raw_memory_pressure", "Raw (pre-EWMA) memory pressure fraction per node"),
+        &["node"],
+    ).unwrap();
+    static ref EWMA_NETWORK: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_ewma_network_saturation", "EWMA-smoothed network saturation fraction per node"),
+        &["node"],
+    ).unwrap();
+    static ref RAW_NETWORK: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_raw_network_saturation", "Raw (pre-EWMA) network saturation fraction per node"),
+        &["node"],
+    ).unwrap();
+
+    // Gauge per-pod (labels: namespace, pod)
+    static ref EWMA_POD_CPU: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_ewma_pod_cpu_saturation", "EWMA-smoothed CPU saturation relative to limit per service pod"),
+        &["namespace", "pod"],
+    ).unwrap();
+    static ref RAW_POD_CPU: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_raw_pod_cpu_saturation", "Raw (pre-EWMA) CPU saturation relative to limit per service pod"),
+        &["namespace", "pod"],
+    ).unwrap();
 
     // Gauge per-node per-service (labels: node, service)
+    static ref RAW_LATENCY: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_raw_latency_ms", "Raw measured response time in ms per node"),
+        &["node", "service"],
+    ).unwrap();
+    static ref LATENCY_SAMPLES: HistogramVec = HistogramVec::new(
+        HistogramOpts::new("proberv2_latency_sample_ms", "Distribution of successful latency probe samples in ms")
+            .buckets(vec![1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0]),
+        &["node", "service"],
+    ).unwrap();
     static ref PERFORMANCE_SCORE: GaugeVec = GaugeVec::new(
-        Opts::new("proberv2_performance_score", "Raw performance score (1-cpu)/latency per node"),
+        Opts::new("proberv2_performance_score", "Raw performance score per node, ((1-cpu)*(1-memory)*(1-network)*(1-podCpu)/latency)^exponent under the powerLaw policy"),
         &["node", "service"],
     ).unwrap();
     static ref SCORE_PERCENTAGE: GaugeVec = GaugeVec::new(
@@ -39,6 +80,14 @@ lazy_static! {
         Opts::new("proberv2_node_eligible", "Whether node is eligible for traffic (1=yes, 0=no)"),
         &["node", "service"],
     ).unwrap();
+    static ref NODE_EJECTED: GaugeVec = GaugeVec::new(
+        Opts::new("proberv2_node_ejected", "Whether node is ejected after failing latency probes (1=yes, 0=no)"),
+        &["node", "service"],
+    ).unwrap();
+    static ref EJECTIONS: IntCounterVec = IntCounterVec::new(
+        Opts::new("proberv2_ejections_total", "Number of outlier ejections per node"),
+        &["node", "service"],
+    ).unwrap();
 
     // Gauge per-service (label: service)
     static ref PROBABILITY_CAP: GaugeVec = GaugeVec::new(
@@ -53,11 +102,22 @@ pub fn init() {
         Box::new(EWMA_CPU.clone()),
         Box::new(EWMA_LATENCY.clone()),
         Box::new(RAW_CPU.clone()),
+        Box::new(EWMA_RTT.clone()),
+        Box::new(RAW_RTT.clone()),
+        Box::new(EWMA_MEMORY.clone()),
+        Box::new(RAW_MEMORY.clone()),
+        Box::new(EWMA_NETWORK.clone()),
+        Box::new(RAW_NETWORK.clone()),
+        Box::new(EWMA_POD_CPU.clone()),
+        Box::new(RAW_POD_CPU.clone()),
         Box::new(RAW_LATENCY.clone()),
+        Box::new(LATENCY_SAMPLES.clone()),
         Box::new(PERFORMANCE_SCORE.clone()),
         Box::new(SCORE_PERCENTAGE.clone()),
         Box::new(NFT_SLOTS.clone()),
         Box::new(NODE_ELIGIBLE.clone()),
+        Box::new(NODE_EJECTED.clone()),
+        Box::new(EJECTIONS.clone()),
         Box::new(PROBABILITY_CAP.clone()),
     ];
     for c in collectors {
@@ -85,16 +145,52 @@ pub fn set_ewma_cpu(node: &str, value: f64) {
     EWMA_CPU.with_label_values(&[node]).set(value);
 }
 
-pub fn set_ewma_latency(node: &str, value: f64) {
-    EWMA_LATENCY.with_label_values(&[node]).set(value);
+pub fn set_ewma_latency(node: &str, service: &str, value: f64) {
+    EWMA_LATENCY.with_label_values(&[node, service]).set(value);
 }
 
 pub fn set_raw_cpu_usage(node: &str, value: f64) {
     RAW_CPU.with_label_values(&[node]).set(value);
 }
 
-pub fn set_raw_latency_ms(node: &str, value: f64) {
-    RAW_LATENCY.with_label_values(&[node]).set(value);
+pub fn set_ewma_rtt(node: &str, value: f64) {
+    EWMA_RTT.with_label_values(&[node]).set(value);
+}
+
+pub fn set_raw_rtt_ms(node: &str, value: f64) {
+    RAW_RTT.with_label_values(&[node]).set(value);
+}
+
+pub fn set_ewma_memory(node: &str, value: f64) {
+    EWMA_MEMORY.with_label_values(&[node]).set(value);
+}
+
+pub fn set_raw_memory_pressure(node: &str, value: f64) {
+    RAW_MEMORY.with_label_values(&[node]).set(value);
+}
+
+pub fn set_ewma_network(node: &str, value: f64) {
+    EWMA_NETWORK.with_label_values(&[node]).set(value);
+}
+
+pub fn set_raw_network_saturation(node: &str, value: f64) {
+    RAW_NETWORK.with_label_values(&[node]).set(value);
+}
+
+pub fn set_ewma_pod_cpu(namespace: &str, pod: &str, value: f64) {
+    EWMA_POD_CPU.with_label_values(&[namespace, pod]).set(value);
+}
+
+pub fn set_raw_pod_cpu(namespace: &str, pod: &str, value: f64) {
+    RAW_POD_CPU.with_label_values(&[namespace, pod]).set(value);
+}
+
+pub fn set_raw_latency_ms(node: &str, service: &str, value: f64) {
+    RAW_LATENCY.with_label_values(&[node, service]).set(value);
+}
+
+pub fn observe_latency_sample_ms(node: &str, service: &str, value: f64) {
+    LATENCY_SAMPLES.with_label_values(&[node, service]).observe(value);
 }
 
 pub fn set_performance_score(node: &str, service: &str, value: f64) {
@@ -113,6 +209,77 @@ pub fn set_node_eligible(node: &str, service: &str, value: f64) {
     NODE_ELIGIBLE.with_label_values(&[node, service]).set(value);
 }
 
+/// Menandai status ejection node, setiap ejection baru juga menambah counter ejection
+pub fn set_node_ejected(node: &str, service: &str, ejected: bool) {
+    NODE_EJECTED
+        .with_label_values(&[node, service])
+        .set(if ejected { 1.0 } else { 0.0 });
+    if ejected {
+        EJECTIONS.with_label_values(&[node, service]).inc();
+    }
+}
+
 pub fn set_probability_cap(service: &str, value: u32) {
     PROBABILITY_CAP.with_label_values(&[service]).set(value as f64);
 }
+
+/// Menghapus semua series milik node yang telah keluar dari cluster agar tidak
+/// terus diekspor dengan nilai terakhirnya
+pub fn remove_node(node: &str, services: impl IntoIterator<Item = impl AsRef<str>>) {
+    for gauge in [
+        &*EWMA_CPU,
+        &*RAW_CPU,
+        &*EWMA_RTT,
+        &*RAW_RTT,
+        &*EWMA_MEMORY,
+        &*RAW_MEMORY,
+        &*EWMA_NETWORK,
+        &*RAW_NETWORK,
+    ] {
+        let _ = gauge.remove_label_values(&[node]);
+    }
+    for service in services {
+        for gauge in [
+            &*EWMA_LATENCY,
+            &*RAW_LATENCY,
+            &*PERFORMANCE_SCORE,
+            &*SCORE_PERCENTAGE,
+            &*NFT_SLOTS,
+            &*NODE_ELIGIBLE,
+            &*NODE_EJECTED,
+        ] {
+            let _ = gauge.remove_label_values(&[node, service.as_ref()]);
+        }
+        let _ = EJECTIONS.remove_label_values(&[node, service.as_ref()]);
+        let _ = LATENCY_SAMPLES.remove_label_values(&[node, service.as_ref()]);
+    }
+}
+
+/// Menghapus series pod yang tidak lagi menjadi endpoint Service
+pub fn remove_pod(namespace: &str, pod: &str) {
+    let _ = EWMA_POD_CPU.remove_label_values(&[namespace, pod]);
+    let _ = RAW_POD_CPU.remove_label_values(&[namespace, pod]);
+}
+
+/// Menghapus series pembagian traffic sebuah Service yang tidak lagi dikendalikan
+pub fn remove_service_steering(service: &str, nodes: impl IntoIterator<Item = impl AsRef<str>>) {
+    let _ = PROBABILITY_CAP.remove_label_values(&[service]);
+    for node in nodes {
+        for gauge in [&*PERFORMANCE_SCORE, &*SCORE_PERCENTAGE, &*NFT_SLOTS, &*NODE_ELIGIBLE] {
+            let _ = gauge.remove_label_values(&[node.as_ref(), service]);
+        }
+    }
+}
+
+/// Menghapus seluruh series milik Service yang telah dihapus atau tidak lagi dipilih
+pub fn remove_service(service: &str, nodes: impl IntoIterator<Item = impl AsRef<str>>) {
+    let nodes: Vec<_> = nodes.into_iter().collect();
+    for node in &nodes {
+        for gauge in [&*EWMA_LATENCY, &*RAW_LATENCY, &*NODE_EJECTED] {
+            let _ = gauge.remove_label_values(&[node.as_ref(), service]);
+        }
+        let _ = EJECTIONS.remove_label_values(&[node.as_ref(), service]);
+        let _ = LATENCY_SAMPLES.remove_label_values(&[node.as_ref(), service]);
+    }
+    remove_service_steering(service, nodes);
+}
@@ -0,0 +1,30 @@
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    actor::{Event, EwmaDatapoint},
+    config::Config,
+    metrics,
+    network_source::network_source,
+    node_signal_probe::{NodeSignal, probe_node_signal},
+};
+
+/// Mengukur saturasi jaringan setiap worker node, uplink yang jenuh atau mulai membuang
+/// paket menambah latency koneksi baru sebelum probe latency Service sempat menangkapnya
+pub async fn probe_network(
+    config: Config,
+    tx: broadcast::Sender<Event>,
+    token: CancellationToken,
+) -> anyhow::Result<()> {
+    let signal = NodeSignal {
+        name: "network saturation",
+        interval: config.probe.network_interval,
+        alpha: config.alpha.ewma_network,
+        source: network_source(&config)?,
+        read: |source, nodes| source.network_saturation(nodes),
+        set_raw: metrics::set_raw_network_saturation,
+        set_ewma: metrics::set_ewma_network,
+        datapoint: EwmaDatapoint::Network,
+    };
+    probe_node_signal(signal, tx, token).await
+}
@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::anyhow;
+use futures::future::BoxFuture;
+use tokio::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::{
+    actor::WorkerNode,
+    config::{Config, NetworkSourceConfig},
+    cpu_source::Prometheus,
+};
+
+/// Sumber saturasi jaringan worker node
+pub trait NetworkSource: Send + Sync {
+    /// Saturasi jaringan (0 hingga 1) setiap node yang berhasil dibaca, Node Name -> saturasi.
+    /// Node yang gagal dibaca tidak disertakan
+    fn network_saturation<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>>;
+}
+
+/// Dua query node_exporter yang dievaluasi sekali setiap tick: utilisasi interface
+/// terhadap kecepatan link dan rasio paket drop atau error
+pub struct PrometheusNetwork {
+    pub utilization: Prometheus,
+    pub errors: Prometheus,
+}
+
+/// Counter `/sys/class/net` milik node tempat prober berjalan, saturasi dihitung
+/// dari selisih counter terhadap pembacaan sebelumnya
+pub struct SysClassNet {
+    pub path: PathBuf,
+    pub node_name: String,
+    previous: Mutex<Option<(Instant, HashMap<String, InterfaceCounters>)>>,
+}
+
+/// Counter sebuah interface jaringan fisik
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InterfaceCounters {
+    /// Kecepatan link dalam Mbit/s, `None` ketika tidak dilaporkan driver
+    pub speed_mbps: Option<f64>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub packets: u64,
+    /// Jumlah paket drop dan error pada kedua arah
+    pub faults: u64,
+}
+
+impl SysClassNet {
+    pub fn new(path: impl Into<PathBuf>, node_name: String) -> Self {
+        Self {
+            path: path.into(),
+            node_name,
+            previous: Mutex::new(None),
+        }
+    }
+}
+
+impl NetworkSource for PrometheusNetwork {
+    fn network_saturation<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>> {
+        Box::pin(async move {
+            let utilization_by_nodename = self.utilization.query_nodes(nodes).await?;
+            // node tanpa paket tidak memiliki series rasio error
+            let errors_by_nodename = match self.errors.query_nodes(nodes).await {
+                Ok(errors_by_nodename) => errors_by_nodename,
+                Err(e) => {
+                    warn!("actor: failed to query nodes network errors: {e}");
+                    HashMap::new()
+                }
+            };
+            Ok(utilization_by_nodename
+                .into_iter()
+                .map(|(nodename, utilization)| {
+                    let errors = errors_by_nodename.get(&nodename).copied().unwrap_or(0.0);
+                    (nodename, combine(utilization, errors))
+                })
+                .collect())
+        })
+    }
+}
+
+impl NetworkSource for SysClassNet {
+    fn network_saturation<'a>(
+        &'a self,
+        nodes: &'a [WorkerNode],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>> {
+        Box::pin(async move {
+            let mut saturation_by_nodename = HashMap::new();
+            // hanya node tempat prober berjalan yang dapat dibaca secara lokal
+            if !nodes.iter().any(|worker| worker.name == self.node_name) {
+                return Ok(saturation_by_nodename);
+            }
+
+            let counters = read_counters(&self.path).await?;
+            let now = Instant::now();
+            let previous = self
+                .previous
+                .lock()
+                .map_err(|_| anyhow!("sys class net state poisoned"))?
+                .replace((now, counters.clone()));
+
+            // pembacaan pertama hanya menjadi acuan bagi pembacaan berikutnya
+            if let Some((then, previous)) = previous {
+                saturation_by_nodename.insert(
+                    self.node_name.clone(),
+                    saturation(&previous, &counters, now - then),
+                );
+            }
+            Ok(saturation_by_nodename)
+        })
+    }
+}
+
+/// Sumber saturasi jaringan yang dipilih pada konfigurasi
+pub fn network_source(config: &Config) -> anyhow::Result<Box<dyn NetworkSource>> {
+    let prometheus = |query: &str| -> anyhow::Result<Prometheus> {
+        Ok(Prometheus {
+            client: prometheus_http_query::Client::try_from(config.prometheus.url.clone())?,
+            query: query.to_string(),
+            join_label: config.prometheus.join_label.clone(),
+            join_by: config.prometheus.join_by,
+        })
+    };
+    Ok(match &config.probe.network_source {
+        NetworkSourceConfig::Prometheus => Box::new(PrometheusNetwork {
+            utilization: prometheus(&config.prometheus.network_utilization_query)?,
+            errors: prometheus(&config.prometheus.network_error_query)?,
+        }),
+        NetworkSourceConfig::SysClassNet { path } => {
+            Box::new(SysClassNet::new(path, config.kubernetes.node_name.clone()))
+        }
+    })
+}
+
+/// Saturasi jaringan dari utilisasi link tertinggi dan rasio paket drop atau error,
+/// sehingga link yang mulai membuang paket dianggap jenuh meskipun throughput-nya rendah
+pub fn combine(utilization: f64, errors: f64) -> f64 {
+    (utilization.max(0.0) + errors.max(0.0)).min(1.0)
+}
+
+/// Membaca counter seluruh interface fisik, interface virtual (loopback, veth, bridge CNI)
+/// tidak memiliki symlink `device` sehingga dilewati
+pub async fn read_counters(path: &Path) -> anyhow::Result<HashMap<String, InterfaceCounters>> {
+    let mut counters = HashMap::new();
+    let mut entries = tokio::fs::read_dir(path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let interface = entry.path();
+        if !tokio::fs::try_exists(interface.join("device")).await? {
+            continue;
+        }
+        let Some(name) = interface.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        // driver melaporkan -1 atau gagal dibaca ketika kecepatan link tidak diketahui
+        let speed_mbps = tokio::fs::read_to_string(interface.join("speed"))
+            .await
+            .ok()
+            .and_then(|speed| speed.trim().parse::<f64>().ok())
+            .filter(|speed| *speed > 0.0);
+        let statistic = |name: &'static str| read_statistic(&interface, name);
+        counters.insert(
+            name.to_string(),
+            InterfaceCounters {
+                speed_mbps,
+                rx_bytes: statistic("rx_bytes").await?,
+                tx_bytes: statistic("tx_bytes").await?,
+                packets: statistic("rx_packets").await? + statistic("tx_packets").await?,
+                faults: statistic("rx_dropped").await?
+                    + statistic("tx_dropped").await?
+                    + statistic("rx_errors").await?
+                    + statistic("tx_errors").await?,
+            },
+        );
+    }
+    Ok(counters)
+}
+
+/// Membaca sebuah counter pada direktori `statistics` interface
+async fn read_statistic(interface: &Path, name: &str) -> anyhow::Result<u64> {
+    let value = tokio::fs::read_to_string(interface.join("statistics").join(name)).await?;
+    Ok(value.trim().parse()?)
+}
+
+/// Saturasi jaringan diantara dua pembacaan counter, utilisasi diambil dari arah dan
+/// interface tersibuk sedangkan rasio drop atau error dihitung dari seluruh interface
+pub fn saturation(
+    previous: &HashMap<String, InterfaceCounters>,
+    current: &HashMap<String, InterfaceCounters>,
+    elapsed: Duration,
+) -> f64 {
+    let seconds = elapsed.as_secs_f64();
+    let mut utilization = 0.0f64;
+    let (mut packets, mut faults) = (0u64, 0u64);
+    for (name, current) in current {
+        let Some(previous) = previous.get(name) else {
+            continue;
+        };
+        packets += current.packets.saturating_sub(previous.packets);
+        faults += current.faults.saturating_sub(previous.faults);
+        let Some(speed_mbps) = current.speed_mbps.filter(|_| seconds > 0.0) else {
+            continue;
+        };
+        let bytes = current
+            .rx_bytes
+            .saturating_sub(previous.rx_bytes)
+            .max(current.tx_bytes.saturating_sub(previous.tx_bytes));
+        utilization = utilization.max(bytes as f64 * 8.0 / (speed_mbps * 1e6 * seconds));
+    }
+    let errors = if packets > 0 {
+        faults as f64 / packets as f64
+    } else {
+        0.0
+    };
+    combine(utilization, errors)
+}
@@ -0,0 +1,58 @@
+use std::sync::Mutex;
+
+use nftables::{helper, schema::Nftables};
+use serde_json::Value;
+
+/// Tujuan penerapan ruleset nftables dalam format JSON libnftables (`nft -j`)
+pub trait NftablesBackend: Send + Sync {
+    fn apply(&self, ruleset: &Value) -> anyhow::Result<()>;
+
+    /// Menerapkan ruleset yang dibangun melalui `nftables::batch::Batch`
+    fn apply_nftables(&self, ruleset: &Nftables) -> anyhow::Result<()> {
+        self.apply(&serde_json::to_value(ruleset)?)
+    }
+}
+
+/// Backend yang menjalankan binary `nft` pada host, membutuhkan capability NET_ADMIN
+pub struct NftCli;
+
+impl NftablesBackend for NftCli {
+    fn apply(&self, ruleset: &Value) -> anyhow::Result<()> {
+        helper::apply_ruleset_raw(
+            ruleset.to_string().as_ref(),
+            None::<&str>,
+            std::iter::empty::<&str>(),
+        )?;
+        Ok(())
+    }
+}
+
+/// Backend in-memory yang hanya mencatat setiap ruleset yang diterapkan,
+/// digunakan untuk pengujian tanpa akses root
+#[derive(Default)]
+pub struct FakeNftables {
+    applied: Mutex<Vec<Value>>,
+}
+
+impl FakeNftables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seluruh ruleset yang telah diterapkan sesuai urutan penerapan
+    pub fn applied(&self) -> Vec<Value> {
+        self.applied.lock().unwrap().clone()
+    }
+
+    /// Mengambil sekaligus mengosongkan catatan ruleset yang telah diterapkan
+    pub fn take(&self) -> Vec<Value> {
+        std::mem::take(&mut *self.applied.lock().unwrap())
+    }
+}
+
+impl NftablesBackend for FakeNftables {
+    fn apply(&self, ruleset: &Value) -> anyhow::Result<()> {
+        self.applied.lock().unwrap().push(ruleset.clone());
+        Ok(())
+    }
+}
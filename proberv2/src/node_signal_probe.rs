@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use futures::future::BoxFuture;
+use tokio::{
+    sync::broadcast,
+    time::{Duration, interval},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+
+use crate::actor::{Event, EwmaDatapoint, WorkerNode, try_next_event};
+
+/// Membaca sinyal seluruh node dari sebuah sumber, Node Name -> nilai sinyal
+pub type ReadNodes<S> =
+    for<'a> fn(&'a S, &'a [WorkerNode]) -> BoxFuture<'a, anyhow::Result<HashMap<String, f64>>>;
+
+/// Sinyal tingkat node yang dibaca secara berkala dan dihaluskan dengan EWMA
+pub struct NodeSignal<S> {
+    /// Nama sinyal pada log, misalnya "cpu usage"
+    pub name: &'static str,
+    /// Interval pembacaan dalam detik
+    pub interval: u64,
+    pub alpha: f64,
+    pub source: S,
+    pub read: ReadNodes<S>,
+    /// Metrik nilai mentah (pre-EWMA) dan nilai EWMA per node
+    pub set_raw: fn(&str, f64),
+    pub set_ewma: fn(&str, f64),
+    /// Datapoint yang dikirim sebagai event EwmaCalculated
+    pub datapoint: fn(f64) -> EwmaDatapoint,
+}
+
+/// Membaca sinyal seluruh worker node setiap interval, menghitung EWMA setiap node dan
+/// mengirimkannya kepada actor. Node yang gagal dibaca dilewati sehingga nilai sebelumnya
+/// tetap digunakan
+pub async fn probe_node_signal<S: Send + Sync>(
+    signal: NodeSignal<S>,
+    tx: broadcast::Sender<Event>,
+    token: CancellationToken,
+) -> anyhow::Result<()> {
+    let mut ticker = interval(Duration::from_secs(signal.interval));
+    let mut nodes = HashMap::<String, WorkerNode>::new();
+    let mut datapoint_by_nodename = HashMap::<String, f64>::new();
+
+    let mut rx = tx.subscribe();
+    'main: loop {
+        // hentikan main loop ketika program shutdown
+        if token.is_cancelled() {
+            info!("actor: exiting {} probe task", signal.name);
+            return Ok(());
+        }
+
+        // mencoba membaca event perubahan anggota Node dari channel,
+        // jika tidak ada lanjut ke baris selanjutnya
+        while let Some(event) = try_next_event(&mut rx) {
+            match event {
+                Event::NodeJoined(node) => {
+                    nodes.insert(node.name.clone(), node);
+                }
+                // skor EWMA sebelumnya berasal dari instance dengan IP lama sehingga ikut direset
+                Event::NodeUpdated(node) => {
+                    datapoint_by_nodename.remove(&node.name);
+                    nodes.insert(node.name.clone(), node);
+                }
+                Event::NodeLeft(node) => {
+                    datapoint_by_nodename.remove(&node.name);
+                    nodes.remove(&node.name);
+                }
+                _ => {}
+            }
+        }
+
+        // membaca sinyal seluruh node dari sumber yang dipilih pada konfigurasi
+        let workers: Vec<WorkerNode> = nodes.values().cloned().collect();
+        let value_by_nodename = match (signal.read)(&signal.source, &workers).await {
+            Ok(value_by_nodename) => value_by_nodename,
+            Err(e) => {
+                error!("actor: failed to read nodes {}: {e}", signal.name);
+                HashMap::new()
+            }
+        };
+
+        for worker in &workers {
+            let Some(value) = value_by_nodename.get(&worker.name).copied() else {
+                continue;
+            };
+            debug!("actor: {} of {} is {value}", signal.name, worker.name);
+            (signal.set_raw)(&worker.name, value);
+
+            let datapoint = match datapoint_by_nodename.get(&worker.name) {
+                // kalkulasi skor EWMA ketika terdapat skor pada titik sebelumnya
+                Some(datapoint) => signal.alpha * value + (1.0 - signal.alpha) * *datapoint,
+                // gunakan nilai mentah sebagai skor EWMA ketika tidak ada skor
+                // pada titik penghitungan sebelumnya
+                None => value,
+            };
+            datapoint_by_nodename.insert(worker.name.clone(), datapoint);
+            (signal.set_ewma)(&worker.name, datapoint);
+
+            if let Err(e) = tx.send(Event::EwmaCalculated(
+                worker.name.clone(),
+                (signal.datapoint)(datapoint),
+            )) {
+                info!("actor: {} probe exiting: {e}", signal.name);
+                break 'main;
+            };
+        }
+
+        // memberhentikan eksekusi loop dalam kurun waktu yang ditentukan
+        ticker.tick().await;
+    }
+
+    Ok(())
+}
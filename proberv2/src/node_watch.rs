@@ -3,97 +3,227 @@ use futures::TryStreamExt;
 use k8s_openapi::api::core::v1::Node;
 use kube::{
     Api, Client, ResourceExt,
-    api::ListParams,
-    core::Expression,
-    runtime::{self, WatchStreamExt, watcher::Config},
+    runtime::{self, WatchStreamExt, watcher},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    pin::pin,
 };
-use std::net::IpAddr;
 use tokio::sync::broadcast;
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
-enum Control<E> {
-    Watcher(E),
-    Stop,
-}
+const WORKER_LABEL: &str = "node-role.kubernetes.io/worker";
+const CONTROL_PLANE_LABEL: &str = "node-role.kubernetes.io/control-plane";
 
 pub async fn watch_nodes(tx: broadcast::Sender<Event>, token: CancellationToken) -> anyhow::Result<()> {
     let client = Client::try_default().await?;
     let api: Api<Node> = Api::all(client);
 
-    // menerapkan filter query untuk api list node agar hanya menampilkan worker node
-    let matcher = ListParams::default()
-        .labels_from(&Expression::DoesNotExist("node-role.kubernetes.io/control-plane".to_string()).into())
-        .labels_from(&Expression::Equal("node-role.kubernetes.io/worker".to_string(), "true".to_string()).into());
-
-    // inisialisasi pencarian worker node pada tahap awal ketika program baru dijalankan,
-    // hasil query akan dimasukkan kedalam channel sebagai event NodeJoined untuk dikonsumsi
-    // proses lain
-    let nodes = api.list(&matcher).await?;
-    for node in nodes {
-        let Some(addrs) = node.status.as_ref().and_then(|status| status.addresses.as_ref()) else {
-            continue;
-        };
-        let Some(a) = addrs.iter().find(|x| x.type_ == "InternalIP") else {
-            continue;
-        };
+    // menerapkan filter label agar watcher hanya menampilkan worker node, node yang tidak lagi
+    // memenuhi filter akan dikirim oleh API server sebagai event Delete
+    let config = watcher::Config::default()
+        .labels(&format!("!{CONTROL_PLANE_LABEL},{WORKER_LABEL}=true"));
+
+    // berlangganan perubahan node untuk menyesuaikan dengan anggota worker node secara real-time,
+    // list awal dari watcher sekaligus menjadi inisialisasi worker node ketika program baru dijalankan
+    let mut stream = pin!(runtime::watcher(api, config).default_backoff());
+
+    let mut nodes = Nodes::default();
 
-        let Ok(ip) = a.address.parse::<IpAddr>() else {
-            warn!("actor: invalid ip {}", a.address);
-            continue;
+    loop {
+        // menunggu sinyal secara blocking diantara sinyal program shutdown atau event dari watcher
+        let event = tokio::select! {
+            _ = token.cancelled() => {
+                info!("actor: exiting node_watch task");
+                return Ok(());
+            },
+            event = stream.try_next() => event?,
+        };
+        let Some(event) = event else {
+            return Ok(());
         };
 
-        // mengirim event NodeJoined dengan informasi nama serta ip node
-        tx.send(Event::NodeJoined(WorkerNode { name: node.name_any(), ip })).ok();
+        let events = nodes.apply(event);
+        for event in events {
+            if let Err(e) = tx.send(event) {
+                // memberhentikan langganan ketika gagal mengirim event pada channel
+                // yang berarti channel telah ditutup karena dalam proses program shutdown
+                info!("actor: stopping node watcher: {e}");
+                return Ok(());
+            }
+        }
     }
+}
+
+/// Worker node yang telah diumumkan, digunakan untuk mendeteksi perubahan alamat serta
+/// node yang hilang ketika watcher melakukan list ulang
+#[derive(Default)]
+struct Nodes {
+    known: HashMap<String, WorkerNode>,
+    relisted: HashSet<String>,
+}
 
-    // berlangganan perubahan node untuk menyesuaikan dengan anggota worker node secara real-time
-    let handler = runtime::watcher(api, Config::default())
-        .applied_objects()
-        .default_backoff()
-        .map_err(Control::Watcher)
-        .try_for_each(|node| {
-            let tx = tx.clone();
-            async move {
-                let Some(status) = &node.status else {
-                    return Ok(());
-                };
-                let Some(addrs) = &status.addresses else {
-                    return Ok(());
-                };
-                let Some(a) = addrs.iter().find(|x| x.type_ == "InternalIP") else {
-                    return Ok(());
-                };
-
-                let Ok(ip) = a.address.parse::<IpAddr>() else {
-                    warn!("actor: invalid ip {}", a.address);
-                    return Ok(());
-                };
-
-                // mengirim event NodeJoined dengan informasi nama serta ip node
-                if let Err(e) = tx.send(Event::NodeJoined(WorkerNode { name: node.name_any(), ip })) {
-                    info!("actor: stopping node watcher: {e}");
-                    // memberhentikan langganan ketika gagal mengirim event NodeJoined pada channel
-                    // yang berarti channel telah ditutup karena dalam proses program shutdown
-                    return Err(Control::Stop);
-                }
-
-                Ok(())
+impl Nodes {
+    /// Menerapkan event watcher, mengembalikan event anggota worker node yang perlu dikirim
+    fn apply(&mut self, event: watcher::Event<Node>) -> Vec<Event> {
+        match event {
+            watcher::Event::Init => {
+                self.relisted.clear();
+                Vec::new()
             }
-        });
-
-    // menunggu sinyal secara blocking diantara sinyal program shutdown atau
-    // langganan perubahan node berhenti untuk memberhentikan fungsi
-    tokio::select! {
-        _ = token.cancelled() => {
-            info!("actor: exiting node_watch task");
-            Ok(())
-        },
-        result = handler => {
-            if let Err(Control::Watcher(e)) = result {
-                return Err(e.into());
+            watcher::Event::InitApply(node) => {
+                self.relisted.insert(node.name_any());
+                self.applied(&node).into_iter().collect()
             }
-            Ok(())
+            watcher::Event::Apply(node) => self.applied(&node).into_iter().collect(),
+            // node yang sebelumnya diketahui namun tidak muncul pada list ulang
+            // dianggap telah dihapus selama watcher terputus
+            watcher::Event::InitDone => {
+                let departed: Vec<String> = self
+                    .known
+                    .keys()
+                    .filter(|name| !self.relisted.contains(*name))
+                    .cloned()
+                    .collect();
+                departed
+                    .into_iter()
+                    .filter_map(|name| self.known.remove(&name))
+                    .map(Event::NodeLeft)
+                    .collect()
+            }
+            watcher::Event::Delete(node) => self
+                .known
+                .remove(&node.name_any())
+                .map(Event::NodeLeft)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Membandingkan node yang diterima watcher dengan node yang telah diketahui, menghasilkan
+    /// NodeJoined untuk node baru, NodeUpdated ketika alamat node berubah, dan NodeLeft ketika
+    /// node tidak lagi memenuhi label worker node
+    fn applied(&mut self, node: &Node) -> Option<Event> {
+        if !is_worker(node) {
+            return self.known.remove(&node.name_any()).map(Event::NodeLeft);
+        }
+        let worker = worker_node(node)?;
+
+        match self.known.insert(worker.name.clone(), worker.clone()) {
+            None => Some(Event::NodeJoined(worker)),
+            Some(previous) if previous != worker => {
+                info!(
+                    "actor: node {} changed address from {} to {}",
+                    worker.name, previous.ip, worker.ip
+                );
+                Some(Event::NodeUpdated(worker))
+            }
+            Some(_) => None,
         }
     }
 }
+
+/// Filter label yang sama dengan label selector watcher, dicek ulang agar perubahan label
+/// yang diterima sebagai Apply tetap mengeluarkan node
+fn is_worker(node: &Node) -> bool {
+    let labels = node.labels();
+    labels.get(WORKER_LABEL).is_some_and(|value| value == "true")
+        && !labels.contains_key(CONTROL_PLANE_LABEL)
+}
+
+fn worker_node(node: &Node) -> Option<WorkerNode> {
+    let addrs = node.status.as_ref()?.addresses.as_ref()?;
+    let a = addrs.iter().find(|x| x.type_ == "InternalIP")?;
+
+    let Ok(ip) = a.address.parse::<IpAddr>() else {
+        warn!("actor: invalid ip {}", a.address);
+        return None;
+    };
+
+    let hostname = addrs
+        .iter()
+        .find(|x| x.type_ == "Hostname")
+        .map_or_else(|| node.name_any(), |x| x.address.clone());
+
+    Some(WorkerNode { name: node.name_any(), ip, hostname })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn node(name: &str, ip: &str, labels: serde_json::Value) -> Node {
+        serde_json::from_value(json!({
+            "metadata": { "name": name, "labels": labels },
+            "status": { "addresses": [{ "type": "InternalIP", "address": ip }] }
+        }))
+        .unwrap()
+    }
+
+    fn worker(name: &str, ip: &str) -> Node {
+        node(name, ip, json!({ WORKER_LABEL: "true" }))
+    }
+
+    /// Nama event beserta node dan IP-nya, misalnya "joined node-a 10.0.0.1"
+    fn describe(events: Vec<Event>) -> Vec<String> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                Event::NodeJoined(node) => format!("joined {} {}", node.name, node.ip),
+                Event::NodeUpdated(node) => format!("updated {} {}", node.name, node.ip),
+                Event::NodeLeft(node) => format!("left {} {}", node.name, node.ip),
+                _ => "other".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn announces_join_update_and_leave() {
+        let mut nodes = Nodes::default();
+
+        let joined = nodes.apply(watcher::Event::Apply(worker("node-a", "10.0.0.1")));
+        let unchanged = nodes.apply(watcher::Event::Apply(worker("node-a", "10.0.0.1")));
+        let updated = nodes.apply(watcher::Event::Apply(worker("node-a", "10.0.0.9")));
+        let left = nodes.apply(watcher::Event::Delete(worker("node-a", "10.0.0.9")));
+
+        assert_eq!(describe(joined), ["joined node-a 10.0.0.1"]);
+        assert!(unchanged.is_empty());
+        assert_eq!(describe(updated), ["updated node-a 10.0.0.9"]);
+        assert_eq!(describe(left), ["left node-a 10.0.0.9"]);
+    }
+
+    #[test]
+    fn ignores_and_evicts_nodes_outside_label_filter() {
+        let mut nodes = Nodes::default();
+        let control_plane = node(
+            "master",
+            "10.0.0.10",
+            json!({ WORKER_LABEL: "true", CONTROL_PLANE_LABEL: "" }),
+        );
+
+        assert!(nodes.apply(watcher::Event::Apply(control_plane)).is_empty());
+        nodes.apply(watcher::Event::Apply(worker("node-a", "10.0.0.1")));
+
+        // label worker dilepas dari node-a
+        let left = nodes.apply(watcher::Event::Apply(node("node-a", "10.0.0.1", json!({}))));
+        assert_eq!(describe(left), ["left node-a 10.0.0.1"]);
+    }
+
+    #[test]
+    fn relist_evicts_nodes_missing_from_the_new_list() {
+        let mut nodes = Nodes::default();
+        nodes.apply(watcher::Event::Apply(worker("node-a", "10.0.0.1")));
+        nodes.apply(watcher::Event::Apply(worker("node-b", "10.0.0.2")));
+
+        nodes.apply(watcher::Event::Init);
+        let relisted = nodes.apply(watcher::Event::InitApply(worker("node-a", "10.0.0.1")));
+        let departed = nodes.apply(watcher::Event::InitDone);
+
+        assert!(relisted.is_empty());
+        assert_eq!(describe(departed), ["left node-b 10.0.0.2"]);
+    }
+}
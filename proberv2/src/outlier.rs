@@ -0,0 +1,124 @@
+use std::collections::{HashMap, VecDeque};
+
+use tokio::time::{Duration, Instant};
+
+use crate::config::OutlierConfig;
+
+/// Perubahan status sebuah target setelah hasil probe dicatat
+#[derive(Debug, PartialEq)]
+pub enum Transition {
+    /// Target dikeluarkan dari pembagian traffic selama durasi tersebut
+    Ejected(Duration),
+    /// Target kembali menerima traffic setelah masa ejection dan probe yang berhasil
+    Readmitted,
+}
+
+/// Pendeteksi outlier pasif ala Envoy untuk setiap target probe (Service Key, Node Name).
+///
+/// Target dikeluarkan setelah sejumlah kegagalan berturut-turut atau ketika rasio kegagalan
+/// pada jendela probe terakhir melampaui batas. Durasi ejection berlipat ganda untuk setiap
+/// ejection berulang, dan target hanya diterima kembali setelah durasi tersebut berakhir
+/// dan sejumlah probe berikutnya berhasil
+pub struct OutlierDetector {
+    config: OutlierConfig,
+    state_by_target: HashMap<(String, String), OutlierState>,
+}
+
+#[derive(Default)]
+struct OutlierState {
+    consecutive_failures: u32,
+    // hasil probe terakhir, `true` berarti berhasil
+    window: VecDeque<bool>,
+    // jumlah ejection yang menentukan durasi ejection berikutnya
+    ejections: u32,
+    ejected_until: Option<Instant>,
+    consecutive_successes: u32,
+    // probe berhasil sejak diterima kembali, untuk meluruhkan jumlah ejection
+    healthy_probes: usize,
+}
+
+impl OutlierDetector {
+    pub fn new(mut config: OutlierConfig) -> Self {
+        // jendela kosong membuat rasio kegagalan NaN sehingga ejection tidak pernah terjadi
+        config.failure_rate_window = config.failure_rate_window.max(1);
+        Self {
+            config,
+            state_by_target: HashMap::new(),
+        }
+    }
+
+    /// Mencatat hasil probe sebuah target, mengembalikan perubahan status jika ada
+    pub fn record(
+        &mut self,
+        target: &(String, String),
+        success: bool,
+        now: Instant,
+    ) -> Option<Transition> {
+        let config = &self.config;
+        let state = self.state_by_target.entry(target.clone()).or_default();
+
+        if let Some(ejected_until) = state.ejected_until {
+            // hasil probe selama masa ejection tidak diperhitungkan
+            if now < ejected_until || !success {
+                state.consecutive_successes = 0;
+                return None;
+            }
+            state.consecutive_successes += 1;
+            if state.consecutive_successes < config.readmit_successes {
+                return None;
+            }
+            state.ejected_until = None;
+            state.consecutive_successes = 0;
+            state.consecutive_failures = 0;
+            state.window.clear();
+            state.healthy_probes = 0;
+            return Some(Transition::Readmitted);
+        }
+
+        state.window.push_back(success);
+        if state.window.len() > config.failure_rate_window {
+            state.window.pop_front();
+        }
+        if success {
+            state.consecutive_failures = 0;
+            state.healthy_probes += 1;
+            // target yang sehat selama satu jendela penuh mengurangi durasi ejection berikutnya
+            if state.healthy_probes >= config.failure_rate_window {
+                state.ejections = state.ejections.saturating_sub(1);
+                state.healthy_probes = 0;
+            }
+            return None;
+        }
+        state.consecutive_failures += 1;
+        state.healthy_probes = 0;
+
+        let failures = state.window.iter().filter(|success| !**success).count();
+        let failure_rate = failures as f64 / state.window.len() as f64;
+        let window_full = state.window.len() >= config.failure_rate_window;
+        if state.consecutive_failures < config.consecutive_failures
+            && !(window_full && failure_rate >= config.failure_rate_threshold)
+        {
+            return None;
+        }
+
+        let base = Duration::from_secs(config.base_ejection_time);
+        let duration = base
+            .saturating_mul(2u32.saturating_pow(state.ejections))
+            .min(Duration::from_secs(config.max_ejection_time));
+        state.ejections += 1;
+        state.ejected_until = Some(now + duration);
+        state.consecutive_successes = 0;
+        Some(Transition::Ejected(duration))
+    }
+
+    pub fn is_ejected(&self, target: &(String, String)) -> bool {
+        self.state_by_target
+            .get(target)
+            .is_some_and(|state| state.ejected_until.is_some())
+    }
+
+    /// Menghapus status target yang tidak memenuhi predikat
+    pub fn retain(&mut self, mut predicate: impl FnMut(&(String, String)) -> bool) {
+        self.state_by_target.retain(|target, _| predicate(target));
+    }
+}
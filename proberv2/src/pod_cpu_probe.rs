@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use tokio::{
+    sync::broadcast,
+    time::{Duration, interval},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+
+use crate::{
+    actor::{Event, EwmaDatapoint, Service, try_next_event},
+    config::Config,
+    metrics,
+    pod_cpu_source::{PodRef, pod_cpu_source},
+};
+
+/// Mengukur saturasi CPU setiap pod endpoint Service agar pod yang mendekati limit atau
+/// ter-throttle menerima porsi traffic yang lebih kecil meskipun node-nya masih longgar
+pub async fn probe_pod_cpu(
+    config: Config,
+    tx: broadcast::Sender<Event>,
+    token: CancellationToken,
+) -> anyhow::Result<()> {
+    let mut ticker = interval(Duration::from_secs(config.probe.pod_cpu_interval));
+    let mut service_by_key = HashMap::<String, Service>::new();
+    let mut datapoint_by_pod = HashMap::<PodRef, f64>::new();
+    let source = pod_cpu_source(&config).await?;
+
+    let mut rx = tx.subscribe();
+    'main: loop {
+        // hentikan main loop ketika program shutdown
+        if token.is_cancelled() {
+            info!("actor: exiting probe_pod_cpu task");
+            return Ok(());
+        }
+
+        while let Some(event) = try_next_event(&mut rx) {
+            match event {
+                Event::ServiceChanged(service) => {
+                    service_by_key.insert(service.key(), service);
+                }
+                Event::ServiceRemoved(service) => {
+                    service_by_key.remove(&service.key());
+                }
+                _ => {}
+            }
+        }
+
+        // (Service Key, IP endpoint, pod), satu pod dapat menjadi endpoint beberapa Service
+        let mut pod_by_endpoint = Vec::new();
+        for (servicekey, service) in &service_by_key {
+            for (nodename, endpoints) in &service.endpoints_by_nodename {
+                for endpoint in endpoints {
+                    let Some(pod) = &endpoint.pod else {
+                        continue;
+                    };
+                    let pod = PodRef {
+                        namespace: service.namespace.clone(),
+                        name: pod.clone(),
+                        node: nodename.clone(),
+                    };
+                    pod_by_endpoint.push((servicekey.clone(), endpoint.ip, pod));
+                }
+            }
+        }
+
+        // EWMA pod yang tidak lagi menjadi endpoint Service manapun sudah usang
+        datapoint_by_pod.retain(|pod, _| {
+            let present = pod_by_endpoint.iter().any(|(_, _, p)| p == pod);
+            if !present {
+                metrics::remove_pod(&pod.namespace, &pod.name);
+            }
+            present
+        });
+
+        let mut pods: Vec<PodRef> = pod_by_endpoint
+            .iter()
+            .map(|(_, _, pod)| pod.clone())
+            .collect();
+        pods.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+        pods.dedup();
+
+        let saturation_by_pod = match source.pod_cpu_saturation(&pods).await {
+            Ok(saturation_by_pod) => saturation_by_pod,
+            Err(e) => {
+                error!("actor: failed to read pods cpu saturation: {e}");
+                HashMap::new()
+            }
+        };
+
+        for pod in &pods {
+            let Some(saturation) = saturation_by_pod.get(pod).copied() else {
+                continue;
+            };
+            debug!(
+                "actor: cpu saturation of pod {}/{} is {saturation}",
+                pod.namespace, pod.name
+            );
+            metrics::set_raw_pod_cpu(&pod.namespace, &pod.name, saturation);
+
+            let alpha = config.alpha.ewma_pod_cpu;
+            let datapoint = match datapoint_by_pod.get(pod) {
+                Some(datapoint) => alpha * saturation + (1.0 - alpha) * *datapoint,
+                None => saturation,
+            };
+            datapoint_by_pod.insert(pod.clone(), datapoint);
+            metrics::set_ewma_pod_cpu(&pod.namespace, &pod.name, datapoint);
+        }
+
+        for (servicekey, ip, pod) in pod_by_endpoint {
+            let Some(datapoint) = datapoint_by_pod.get(&pod).copied() else {
+                continue;
+            };
+            if let Err(e) = tx.send(Event::EndpointEwmaCalculated(
+                servicekey,
+                ip,
+                EwmaDatapoint::PodCpu(datapoint),
+            )) {
+                info!("actor: pod cpu probe exiting: {e}");
+                break 'main;
+            };
+        }
+
+        ticker.tick().await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::bail;
+use axum::http;
+use futures::future::{BoxFuture, join_all};
+use k8s_openapi::api::core::v1::Pod;
+use kube::{Api, ResourceExt, api::ListParams};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{
+    config::{Config, PodCpuSourceConfig},
+    cpu_source::parse_cpu_quantity,
+};
+
+/// Pod endpoint Service beserta node tempat pod tersebut berjalan
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PodRef {
+    pub namespace: String,
+    pub name: String,
+    pub node: String,
+}
+
+/// Sumber pemakaian CPU pod Service
+pub trait PodCpuSource: Send + Sync {
+    /// Saturasi CPU (0 hingga 1) setiap pod yang berhasil dibaca, relatif terhadap limit CPU
+    /// pod. Pod yang gagal dibaca atau tidak memiliki limit tidak disertakan
+    fn pod_cpu_saturation<'a>(
+        &'a self,
+        pods: &'a [PodRef],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<PodRef, f64>>>;
+}
+
+/// Dua query cAdvisor yang dievaluasi sekali setiap tick: pemakaian CPU terhadap limit
+/// dan rasio periode CFS yang ter-throttle, series dicocokkan melalui label `namespace`
+/// dan `pod`
+pub struct PrometheusPodCpu {
+    pub client: prometheus_http_query::Client,
+    pub usage_query: String,
+    pub throttling_query: String,
+}
+
+/// Endpoint `/stats/summary` milik kubelet melalui proxy API server, dibandingkan
+/// dengan jumlah limit CPU container pada spec Pod
+pub struct KubeletPodSummary {
+    pub client: kube::Client,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    #[serde(default)]
+    pods: Vec<SummaryPod>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryPod {
+    pod_ref: SummaryPodRef,
+    cpu: Option<SummaryCpu>,
+}
+
+#[derive(Deserialize)]
+struct SummaryPodRef {
+    name: String,
+    namespace: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryCpu {
+    usage_nano_cores: Option<u64>,
+}
+
+impl PrometheusPodCpu {
+    /// Mengevaluasi query untuk seluruh pod, pod -> nilai series yang cocok
+    async fn query_pods(
+        &self,
+        query: &str,
+        pods: &[PodRef],
+    ) -> anyhow::Result<HashMap<PodRef, f64>> {
+        let query = query.replace("{{pods}}", &targets(pods));
+        let response = self.client.query(query).get().await?;
+        let Some(datas) = response.data().as_vector() else {
+            bail!("promql result is not a vector: {response:?}");
+        };
+
+        let mut value_by_pod = HashMap::new();
+        for data in datas {
+            let (Some(namespace), Some(name)) =
+                (data.metric().get("namespace"), data.metric().get("pod"))
+            else {
+                continue;
+            };
+            let Some(pod) = pods
+                .iter()
+                .find(|pod| pod.namespace == *namespace && pod.name == *name)
+            else {
+                continue;
+            };
+            value_by_pod.insert(pod.clone(), data.sample().value());
+        }
+        Ok(value_by_pod)
+    }
+}
+
+impl PodCpuSource for PrometheusPodCpu {
+    fn pod_cpu_saturation<'a>(
+        &'a self,
+        pods: &'a [PodRef],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<PodRef, f64>>> {
+        Box::pin(async move {
+            if pods.is_empty() {
+                return Ok(HashMap::new());
+            }
+            // pod tanpa limit CPU tidak memiliki series pemakaian terhadap limit namun tetap
+            // dapat ter-throttle, begitu pula sebaliknya
+            let usage_by_pod = self.query_pods(&self.usage_query, pods).await?;
+            let throttling_by_pod = match self.query_pods(&self.throttling_query, pods).await {
+                Ok(throttling_by_pod) => throttling_by_pod,
+                Err(e) => {
+                    warn!("actor: failed to query pods cpu throttling: {e}");
+                    HashMap::new()
+                }
+            };
+
+            let mut saturation_by_pod = HashMap::new();
+            for pod in pods {
+                let usage = usage_by_pod.get(pod).copied();
+                let throttling = throttling_by_pod.get(pod).copied();
+                let saturation = match (usage, throttling) {
+                    (Some(usage), Some(throttling)) => usage.max(throttling),
+                    (Some(value), None) | (None, Some(value)) => value,
+                    (None, None) => {
+                        warn!(
+                            "actor: empty promql result for pod {}/{}",
+                            pod.namespace, pod.name
+                        );
+                        continue;
+                    }
+                };
+                saturation_by_pod.insert(pod.clone(), saturation.clamp(0.0, 1.0));
+            }
+            Ok(saturation_by_pod)
+        })
+    }
+}
+
+impl PodCpuSource for KubeletPodSummary {
+    fn pod_cpu_saturation<'a>(
+        &'a self,
+        pods: &'a [PodRef],
+    ) -> BoxFuture<'a, anyhow::Result<HashMap<PodRef, f64>>> {
+        Box::pin(async move {
+            let namespaces: HashSet<&str> = pods.iter().map(|pod| pod.namespace.as_str()).collect();
+            let mut limit_by_pod = HashMap::new();
+            for namespace in namespaces {
+                limit_by_pod.extend(cpu_limit(&self.client, namespace).await?);
+            }
+
+            // setiap node cukup dibaca sekali untuk seluruh pod yang berjalan di atasnya
+            let nodes: HashSet<&str> = pods.iter().map(|pod| pod.node.as_str()).collect();
+            let summaries = join_all(nodes.into_iter().map(|node| async move {
+                let url = format!("/api/v1/nodes/{node}/proxy/stats/summary");
+                let request = http::Request::get(url).body(Vec::new())?;
+                let summary = self.client.request::<Summary>(request).await;
+                anyhow::Ok((node, summary?))
+            }))
+            .await;
+
+            let mut usage_by_pod = HashMap::new();
+            for summary in summaries {
+                let (node, summary) = match summary {
+                    Ok(summary) => summary,
+                    Err(e) => {
+                        warn!("actor: failed to read kubelet pod summary: {e}");
+                        continue;
+                    }
+                };
+                for pod in summary.pods {
+                    let Some(usage_nano_cores) = pod.cpu.and_then(|cpu| cpu.usage_nano_cores)
+                    else {
+                        continue;
+                    };
+                    usage_by_pod.insert(
+                        (node, pod.pod_ref.namespace, pod.pod_ref.name),
+                        usage_nano_cores,
+                    );
+                }
+            }
+
+            let mut saturation_by_pod = HashMap::new();
+            for pod in pods {
+                let key = (pod.node.as_str(), pod.namespace.clone(), pod.name.clone());
+                let Some(usage_nano_cores) = usage_by_pod.get(&key) else {
+                    continue;
+                };
+                let Some(limit) = limit_by_pod.get(&(pod.namespace.clone(), pod.name.clone()))
+                else {
+                    continue;
+                };
+                saturation_by_pod.insert(
+                    pod.clone(),
+                    (*usage_nano_cores as f64 / 1e9 / limit).clamp(0.0, 1.0),
+                );
+            }
+            Ok(saturation_by_pod)
+        })
+    }
+}
+
+/// Sumber pemakaian CPU pod yang dipilih pada konfigurasi
+pub async fn pod_cpu_source(config: &Config) -> anyhow::Result<Box<dyn PodCpuSource>> {
+    Ok(match &config.probe.pod_cpu_source {
+        PodCpuSourceConfig::Prometheus => Box::new(PrometheusPodCpu {
+            client: prometheus_http_query::Client::try_from(config.prometheus.url.clone())?,
+            usage_query: config.prometheus.pod_cpu_usage_query.clone(),
+            throttling_query: config.prometheus.pod_cpu_throttling_query.clone(),
+        }),
+        PodCpuSourceConfig::KubeletSummary => Box::new(KubeletPodSummary {
+            client: kube::Client::try_default().await?,
+        }),
+    })
+}
+
+/// Regex alternatif nama seluruh pod untuk `{{pods}}`, dengan escape backslash agar
+/// tetap valid di dalam string PromQL
+fn targets(pods: &[PodRef]) -> String {
+    let mut names: Vec<String> = pods
+        .iter()
+        .map(|pod| regex::escape(&pod.name).replace('\\', "\\\\"))
+        .collect();
+    names.sort();
+    names.dedup();
+    format!("({})", names.join("|"))
+}
+
+/// Limit CPU setiap pod pada namespace dalam satuan core, (namespace, nama Pod) -> core.
+/// Pod yang salah satu container-nya tidak memiliki limit tidak disertakan karena
+/// pemakaiannya tidak memiliki batas atas
+async fn cpu_limit(
+    client: &kube::Client,
+    namespace: &str,
+) -> anyhow::Result<HashMap<(String, String), f64>> {
+    let pods = Api::<Pod>::namespaced(client.clone(), namespace)
+        .list(&ListParams::default())
+        .await?;
+    Ok(pods
+        .into_iter()
+        .filter_map(|pod| {
+            let mut limit = 0.0;
+            for container in &pod.spec.as_ref()?.containers {
+                let cpu = container.resources.as_ref()?.limits.as_ref()?.get("cpu")?;
+                limit += parse_cpu_quantity(&cpu.0)?;
+            }
+            (limit > 0.0).then(|| ((namespace.to_string(), pod.name_any()), limit))
+        })
+        .collect())
+}
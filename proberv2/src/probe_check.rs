@@ -0,0 +1,57 @@
+use std::net::SocketAddr;
+
+use anyhow::anyhow;
+use reqwest::Client;
+use tokio::{
+    net::TcpStream,
+    time::{Duration, timeout},
+};
+
+use crate::{
+    config::{HttpProbeConfig, ProbeCheckConfig, ProbeMode},
+    grpc_probe::GrpcCheck,
+    http_probe::HttpCheck,
+};
+
+/// Cara probe latency mengukur sebuah endpoint Service
+#[derive(Debug)]
+pub enum ProbeCheck {
+    Http(Box<HttpCheck>),
+    /// Hanya mengukur three-way handshake TCP, untuk workload yang tidak berbicara HTTP
+    Tcp {
+        connect_timeout: Duration,
+    },
+    /// Memanggil RPC health standar pada backend gRPC
+    Grpc(GrpcCheck),
+}
+
+impl ProbeCheck {
+    pub fn from_config(config: &ProbeCheckConfig, http: &HttpProbeConfig) -> anyhow::Result<Self> {
+        Ok(match config.mode {
+            ProbeMode::Http => ProbeCheck::Http(Box::new(HttpCheck::from_config(&config.http)?)),
+            ProbeMode::Tcp => ProbeCheck::Tcp {
+                connect_timeout: Duration::from_millis(http.connect_timeout_ms),
+            },
+            ProbeMode::Grpc => ProbeCheck::Grpc(GrpcCheck {
+                service: config.grpc_service.clone(),
+                timeout: Duration::from_millis(http.timeout_ms),
+                connect_timeout: Duration::from_millis(http.connect_timeout_ms),
+            }),
+        })
+    }
+
+    /// Melakukan probe terhadap endpoint, `Ok` berarti endpoint dianggap sehat
+    pub async fn probe(&self, client: &Client, address: SocketAddr) -> anyhow::Result<()> {
+        match self {
+            ProbeCheck::Http(check) => check.probe(client, address).await,
+            ProbeCheck::Tcp { connect_timeout } => {
+                // koneksi langsung ditutup setelah handshake selesai
+                timeout(*connect_timeout, TcpStream::connect(address))
+                    .await
+                    .map_err(|_| anyhow!("connect timed out after {connect_timeout:?}"))??;
+                Ok(())
+            }
+            ProbeCheck::Grpc(check) => check.probe(address).await,
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use anyhow::bail;
+
+use crate::{
+    actor::ScorePair,
+    config::{Config, ScoringConfig},
+};
+
+/// Kebijakan yang mengubah datapoint latency dan CPU setiap node menjadi skor,
+/// porsi traffic sebuah node sebanding dengan skornya terhadap total skor
+pub trait ScoringPolicy: Send + Sync {
+    /// Skor setiap datapoint sesuai urutan masukan, skor yang lebih besar menerima lebih banyak traffic
+    fn scores(&self, datapoints: &[&ScorePair]) -> Vec<f64>;
+}
+
+/// `((1 - cpu) * (1 - memory) * (1 - network) * (1 - podCpu) / latency)^exponent`, kebijakan
+/// bawaan sejak versi pertama. Tekanan memori, saturasi jaringan dan saturasi CPU pod yang
+/// belum terukur bernilai 0 sehingga tidak mengubah skor
+pub struct PowerLaw {
+    pub exponent: f64,
+}
+
+/// Kombinasi linear sinyal yang dinormalisasi kedalam rentang (0, 1], mengikuti model
+/// `WeightLatency`/`WeightCPU` pada scheduler extender. RTT jaringan dapat diberi bobot
+/// tersendiri, node yang RTT-nya belum terukur dianggap setara dengan node terdekat.
+/// Tekanan memori, saturasi jaringan dan saturasi CPU pod juga dapat diberi bobot tersendiri
+/// sebagai `1 - memory`, `1 - network` dan `1 - podCpu`
+pub struct WeightedLinear {
+    pub weight_latency: f64,
+    pub weight_cpu: f64,
+    pub weight_rtt: f64,
+    pub weight_memory: f64,
+    pub weight_network: f64,
+    pub weight_pod_cpu: f64,
+}
+
+/// `1 / latency`, mengabaikan penggunaan CPU
+pub struct InverseLatency;
+
+/// Softmax dari kombinasi sinyal yang dinormalisasi, temperature yang kecil
+/// memusatkan traffic pada node terbaik sedangkan yang besar meratakannya
+pub struct Softmax {
+    pub temperature: f64,
+}
+
+impl ScoringPolicy for PowerLaw {
+    fn scores(&self, datapoints: &[&ScorePair]) -> Vec<f64> {
+        datapoints
+            .iter()
+            .map(|datapoint| {
+                ((1.0 - datapoint.cpu)
+                    * (1.0 - datapoint.memory)
+                    * (1.0 - datapoint.network)
+                    * (1.0 - datapoint.pod_cpu)
+                    / datapoint.latency)
+                    .powf(self.exponent)
+            })
+            .collect()
+    }
+}
+
+impl ScoringPolicy for WeightedLinear {
+    fn scores(&self, datapoints: &[&ScorePair]) -> Vec<f64> {
+        let fastest = fastest_latency(datapoints);
+        let nearest = datapoints
+            .iter()
+            .map(|datapoint| datapoint.rtt)
+            .filter(|rtt| *rtt > 0.0)
+            .fold(f64::INFINITY, f64::min);
+        datapoints
+            .iter()
+            .map(|datapoint| {
+                let rtt = if datapoint.rtt > 0.0 {
+                    nearest / datapoint.rtt
+                } else {
+                    1.0
+                };
+                self.weight_latency * (fastest / datapoint.latency)
+                    + self.weight_cpu * (1.0 - datapoint.cpu)
+                    + self.weight_rtt * rtt
+                    + self.weight_memory * (1.0 - datapoint.memory)
+                    + self.weight_network * (1.0 - datapoint.network)
+                    + self.weight_pod_cpu * (1.0 - datapoint.pod_cpu)
+            })
+            .collect()
+    }
+}
+
+impl ScoringPolicy for InverseLatency {
+    fn scores(&self, datapoints: &[&ScorePair]) -> Vec<f64> {
+        datapoints
+            .iter()
+            .map(|datapoint| 1.0 / datapoint.latency)
+            .collect()
+    }
+}
+
+impl ScoringPolicy for Softmax {
+    fn scores(&self, datapoints: &[&ScorePair]) -> Vec<f64> {
+        let fastest = fastest_latency(datapoints);
+        let utilities: Vec<f64> = datapoints
+            .iter()
+            .map(|datapoint| {
+                (1.0 - datapoint.cpu)
+                    * (1.0 - datapoint.memory)
+                    * (1.0 - datapoint.network)
+                    * (1.0 - datapoint.pod_cpu)
+                    * (fastest / datapoint.latency)
+            })
+            .collect();
+        // dikurangi utilitas terbesar agar exp tidak overflow pada temperature kecil
+        let best = utilities.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        utilities
+            .into_iter()
+            .map(|utility| ((utility - best) / self.temperature).exp())
+            .collect()
+    }
+}
+
+/// Latency terkecil sebagai acuan normalisasi latency kedalam rentang (0, 1]
+fn fastest_latency(datapoints: &[&ScorePair]) -> f64 {
+    datapoints
+        .iter()
+        .map(|datapoint| datapoint.latency)
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Kebijakan scoring yang dipilih pada konfigurasi, parameter yang menghasilkan porsi
+/// tidak valid (temperature softmax bukan positif, eksponen tidak finite) ditolak
+pub fn scoring_policy(config: &Config) -> anyhow::Result<Box<dyn ScoringPolicy>> {
+    Ok(match config.scoring {
+        ScoringConfig::PowerLaw => {
+            if !config.alpha.score_exponent.is_finite() {
+                bail!(
+                    "power law score exponent must be finite, got {}",
+                    config.alpha.score_exponent
+                );
+            }
+            Box::new(PowerLaw {
+                exponent: config.alpha.score_exponent,
+            })
+        }
+        ScoringConfig::WeightedLinear {
+            weight_latency,
+            weight_cpu,
+            weight_rtt,
+            weight_memory,
+            weight_network,
+            weight_pod_cpu,
+        } => Box::new(WeightedLinear {
+            weight_latency,
+            weight_cpu,
+            weight_rtt,
+            weight_memory,
+            weight_network,
+            weight_pod_cpu,
+        }),
+        ScoringConfig::InverseLatency => Box::new(InverseLatency),
+        ScoringConfig::Softmax { temperature } => {
+            // temperature 0 membuat utilitas terbaik menjadi NaN dan porsi seluruh node tidak valid
+            if !(temperature.is_finite() && temperature > 0.0) {
+                bail!("softmax temperature must be positive, got {temperature}");
+            }
+            Box::new(Softmax { temperature })
+        }
+    })
+}
@@ -4,7 +4,6 @@ use kube::{Api, Client, ResourceExt};
 use nftables::{
     batch::Batch,
     expr::Expression as NftExpression,
-    helper,
     schema::{Chain, NfListObject, Rule, Set, SetType, SetTypeValue, Table},
     stmt::{JumpTarget, Statement},
     types::{NfChainPolicy, NfChainType, NfFamily, NfHook},
@@ -13,9 +12,9 @@ use serde_json::json;
 use std::{borrow::Cow, net::IpAddr};
 use tracing::{debug, info};
 
-use crate::config::Config;
+use crate::{config::Config, nftables_backend::NftablesBackend};
 
-pub async fn setup_nftables(config: &Config) -> anyhow::Result<()> {
+pub async fn setup_nftables(config: &Config, backend: &dyn NftablesBackend) -> anyhow::Result<()> {
     // inisialisasi klien API Node
     info!("actor: configuring base nftables ruleset");
     let client = Client::try_default().await?;
@@ -33,34 +32,49 @@ pub async fn setup_nftables(config: &Config) -> anyhow::Result<()> {
             node.name_any()
         ));
     };
-    let Some(a) = addresses
+    // node dual-stack memiliki satu InternalIP untuk setiap family alamat
+    let ips = addresses
         .iter()
-        .find(|address| address.type_ == "InternalIP")
-    else {
+        .filter(|address| address.type_ == "InternalIP")
+        .map(|address| address.address.parse::<IpAddr>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if ips.is_empty() {
         return Err(anyhow!(
             "missing node {} InternalIP address",
             node.name_any()
         ));
-    };
-    let ip = a.address.parse::<IpAddr>()?;
+    }
 
-    // menghapus tabel kustom yang telah dibuat jika program telah berjalan sebelumnya
-    let mut batch = Batch::new();
-    batch.delete(NfListObject::Table(Table {
-        name: config.nftables.table.clone().into(),
-        family: NfFamily::IP,
-        ..Default::default()
-    }));
-    let ruleset = batch.to_nftables();
-    // mengabaikan error menghapus tabel jika tidak ada
-    let _ = helper::apply_ruleset(&ruleset);
+    install_base_ruleset(config, backend, &ips)
+}
+
+/// Membangun ulang tabel kustom beserta map, set, dan chain dasar
+/// untuk node dengan daftar InternalIP yang diberikan
+pub fn install_base_ruleset(
+    config: &Config,
+    backend: &dyn NftablesBackend,
+    ips: &[IpAddr],
+) -> anyhow::Result<()> {
+    // menghapus tabel kustom yang telah dibuat jika program telah berjalan sebelumnya,
+    // termasuk tabel family ip yang dibuat oleh versi sebelum dukungan dual-stack
+    for family in [NfFamily::IP, NfFamily::INet] {
+        let mut batch = Batch::new();
+        batch.delete(NfListObject::Table(Table {
+            name: config.nftables.table.clone().into(),
+            family,
+            ..Default::default()
+        }));
+        let ruleset = batch.to_nftables();
+        // mengabaikan error menghapus tabel jika tidak ada
+        let _ = backend.apply_nftables(&ruleset);
+    }
 
     // membuat tabel kustom dengan nama spesifik dari konfigurasi yang akan digunakan
     // untuk menampung aturan packet forwarding kustom yang akan diimplementasikan nantinya
     let mut batch = Batch::new();
     batch.add(NfListObject::Table(Table {
         name: config.nftables.table.clone().into(),
-        family: NfFamily::IP,
+        family: NfFamily::INet,
         ..Default::default()
     }));
     let ruleset = batch.to_nftables();
@@ -68,64 +82,77 @@ pub async fn setup_nftables(config: &Config) -> anyhow::Result<()> {
         "actor: applying table: {}",
         serde_json::to_string(&ruleset)?
     );
-    helper::apply_ruleset(&ruleset)?;
+    backend.apply_nftables(&ruleset)?;
 
     // menambahkan struktur data map (service_chain_by_nodeport) yang akan mengandung port dari
     // NodePort sebagai key dan chain packet forwarding spesifik dari Service sebagai value
-    helper::apply_ruleset_raw(
-        json!({
-          "nftables": [
-            {
-              "add": {
-                "map": {
-                  "family": "ip",
-                  "table": config.nftables.table,
-                  "name": config.nftables.map_service_chain_by_nodeport,
-                  "type": [
-                    "inet_proto",
-                    "inet_service"
-                  ],
-                  "map": "verdict",
-                  "comment": "VERDICTS! MUAHAHAHAHA"
-                }
-              }
+    backend.apply(&json!({
+      "nftables": [
+        {
+          "add": {
+            "map": {
+              "family": "inet",
+              "table": config.nftables.table,
+              "name": config.nftables.map_service_chain_by_nodeport,
+              "type": [
+                "inet_proto",
+                "inet_service"
+              ],
+              "map": "verdict",
+              "comment": "VERDICTS! MUAHAHAHAHA"
             }
-          ]
-        })
-        .to_string()
-        .as_ref(),
-        None::<&str>,
-        std::iter::empty::<&str>(),
-    )?;
+          }
+        }
+      ]
+    }))?;
 
     // membuat struktur data Set (allowed_node_ips) untuk memastikan packet forwarding
-    // hanya dilakukan pada port yang terdaftar sebagai Service NodePort
+    // hanya dilakukan pada port yang terdaftar sebagai Service NodePort, tabel inet
+    // membutuhkan set terpisah untuk alamat IPv4 dan IPv6
     let mut batch = Batch::new();
-    let ip_sets = [NftExpression::String(ip.to_string().into())];
-    batch.add(NfListObject::Set(
-        Set {
-            family: NfFamily::IP,
-            table: config.nftables.table.clone().into(),
-            name: config.nftables.set_allowed_node_ips.clone().into(),
-            set_type: SetTypeValue::Single(SetType::Ipv4Addr),
-            comment: Some("List IPv4 yang nerima traffic dari NodePort".into()),
-            elem: Some(ip_sets.as_ref().into()),
-            ..Default::default()
-        }
-        .into(),
-    ));
+    for (name, set_type, comment, ips) in [
+        (
+            &config.nftables.set_allowed_node_ips,
+            SetType::Ipv4Addr,
+            "List IPv4 yang nerima traffic dari NodePort",
+            ips.iter().filter(|ip| ip.is_ipv4()).collect::<Vec<_>>(),
+        ),
+        (
+            &config.nftables.set_allowed_node_ipv6s,
+            SetType::Ipv6Addr,
+            "List IPv6 yang nerima traffic dari NodePort",
+            ips.iter().filter(|ip| ip.is_ipv6()).collect::<Vec<_>>(),
+        ),
+    ] {
+        let ip_sets: Vec<NftExpression> = ips
+            .iter()
+            .map(|ip| NftExpression::String(ip.to_string().into()))
+            .collect();
+        batch.add(NfListObject::Set(
+            Set {
+                family: NfFamily::INet,
+                table: config.nftables.table.clone().into(),
+                name: name.clone().into(),
+                set_type: SetTypeValue::Single(set_type),
+                comment: Some(comment.into()),
+                elem: (!ip_sets.is_empty()).then(|| ip_sets.into()),
+                ..Default::default()
+            }
+            .into(),
+        ));
+    }
     let ruleset = batch.to_nftables();
     debug!(
         "actor: applying set ruleset: {}",
         serde_json::to_string(&ruleset)?
     );
-    helper::apply_ruleset(&ruleset)?;
+    backend.apply_nftables(&ruleset)?;
 
     let mut batch = Batch::new();
     // membuat chain khusus (prerouting) yang dieksekusi tepat sebelum chain dari kubernetes dijalan
     // dengan menggunakan prioritas yang lebih tinggi
     batch.add(NfListObject::Chain(Chain {
-        family: NfFamily::IP,
+        family: NfFamily::INet,
         table: config.nftables.table.clone().into(),
         name: config.nftables.chain_prerouting.clone().into(),
         _type: NfChainType::NAT.into(),
@@ -139,7 +166,7 @@ pub async fn setup_nftables(config: &Config) -> anyhow::Result<()> {
     // 2. mengarahkan paket pada aturan chain yang sesuai berdasarkan port yang dituju
     //    menggunakan lookup O(1) melalui map (service_by_nodeport)
     batch.add(NfListObject::Chain(Chain {
-        family: NfFamily::IP,
+        family: NfFamily::INet,
         table: config.nftables.table.clone().into(),
         name: config.nftables.chain_services.clone().into(),
         ..Default::default()
@@ -149,13 +176,13 @@ pub async fn setup_nftables(config: &Config) -> anyhow::Result<()> {
         "actor: applying chain: {}",
         serde_json::to_string(&ruleset)?
     );
-    helper::apply_ruleset(&ruleset)?;
+    backend.apply_nftables(&ruleset)?;
 
     // menambahkan aturan pada chain (prerouting) untuk
     // melanjutkan pemrosesan paket kepada chain (services)
     let mut batch = Batch::new();
     batch.add(NfListObject::Rule(Rule {
-        family: NfFamily::IP,
+        family: NfFamily::INet,
         table: config.nftables.table.clone().into(),
         chain: config.nftables.chain_prerouting.clone().into(),
         expr: Cow::Owned(vec![Statement::Jump(JumpTarget {
@@ -168,31 +195,35 @@ pub async fn setup_nftables(config: &Config) -> anyhow::Result<()> {
         "actor: applying chain: {}",
         serde_json::to_string(&ruleset)?
     );
-    helper::apply_ruleset(&ruleset)?;
+    backend.apply_nftables(&ruleset)?;
 
-    // menambahkan aturan pada chain (services) untuk melakukan
-    // lookup chain yang dituju oleh paket berdasarkan destinasi port
-    let rule = json!(
-    {
-      "nftables": [
+    // menambahkan aturan pada chain (services) untuk melakukan lookup chain yang dituju
+    // oleh paket berdasarkan destinasi port, satu aturan untuk setiap family alamat
+    let rules: Vec<_> = [
+        ("ip", &config.nftables.set_allowed_node_ips, "Cek IPv4 paket di list IPv4 NodePort, kalo ada langsung ke verdict map ke service yang sesuai"),
+        ("ip6", &config.nftables.set_allowed_node_ipv6s, "Cek IPv6 paket di list IPv6 NodePort, kalo ada langsung ke verdict map ke service yang sesuai"),
+    ]
+    .into_iter()
+    .map(|(protocol, set, comment)| {
+        json!(
         {
           "add": {
             "rule": {
-              "family": "ip",
+              "family": "inet",
               "table": config.nftables.table,
               "chain": config.nftables.chain_services,
-              "comment": "Cek IPv4 paket di list IPv4 NodePort, kalo ada langsung ke verdict map ke service yang sesuai",
+              "comment": comment,
               "expr": [
                 {
                   "match": {
                     "op": "==",
                     "left": {
                       "payload": {
-                        "protocol": "ip",
+                        "protocol": protocol,
                         "field": "daddr"
                       }
                     },
-                    "right": format!("@{}", config.nftables.set_allowed_node_ips)
+                    "right": format!("@{}", set)
                   }
                 },
                 {
@@ -218,13 +249,13 @@ pub async fn setup_nftables(config: &Config) -> anyhow::Result<()> {
               ]
             }
           }
-        }
-      ]
-    }
-    );
+        })
+    })
+    .collect();
+    let rule = json!({ "nftables": rules });
 
     debug!("actor: applying initial ruleset: {}", rule.to_string());
-    helper::apply_ruleset_raw(&rule.to_string(), None::<&str>, std::iter::empty::<&str>())?;
+    backend.apply(&rule)?;
 
     Ok(())
 }
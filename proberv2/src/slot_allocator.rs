@@ -0,0 +1,95 @@
+/// Rentang slot numgen `[start, end]` (inklusif) yang diarahkan kepada sebuah target
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlotRange<T> {
+    pub start: u32,
+    pub end: u32,
+    pub target: T,
+}
+
+/// Membagi tepat `cap` slot kepada setiap bobot menggunakan metode largest remainder.
+///
+/// Bobot yang tidak positif atau tidak finite tidak mendapatkan slot. Ketika `cap` mencukupi,
+/// setiap bobot positif dijamin memperoleh minimal satu slot. Hasil hanya bergantung pada
+/// nilai dan urutan masukan, seri diputuskan berdasarkan urutan masukan
+pub fn slots(weights: &[f64], cap: u32) -> Vec<u32> {
+    let mut slots = vec![0u32; weights.len()];
+    let eligible: Vec<usize> = (0..weights.len())
+        .filter(|&idx| weights[idx].is_finite() && weights[idx] > 0.0)
+        .collect();
+    if eligible.is_empty() || cap == 0 {
+        return slots;
+    }
+
+    // kuota setiap bobot dibulatkan kebawah, sisa slot diberikan kepada
+    // bobot dengan pecahan kuota terbesar
+    let total: f64 = eligible.iter().map(|&idx| weights[idx]).sum();
+    let mut fractions = Vec::with_capacity(eligible.len());
+    for &idx in &eligible {
+        let quota = weights[idx] / total * cap as f64;
+        let floor = quota.floor().min(cap as f64);
+        slots[idx] = floor as u32;
+        fractions.push((idx, quota - floor));
+    }
+    fractions.sort_by(|(a_idx, a), (b_idx, b)| b.total_cmp(a).then(a_idx.cmp(b_idx)));
+
+    // galat floating point dapat membuat total hasil pembulatan sedikit meleset dari cap
+    let mut assigned: u32 = slots.iter().sum();
+    for &(idx, _) in fractions.iter().cycle() {
+        if assigned == cap {
+            break;
+        }
+        if assigned < cap {
+            slots[idx] += 1;
+            assigned += 1;
+        } else if slots[idx] > 0 {
+            slots[idx] -= 1;
+            assigned -= 1;
+        }
+    }
+
+    // bobot yang terlalu kecil untuk memperoleh slot mengambil satu slot
+    // dari pemilik slot terbanyak, selama cap mencukupi
+    if eligible.len() <= cap as usize {
+        for &idx in &eligible {
+            if slots[idx] > 0 {
+                continue;
+            }
+            let donor = eligible
+                .iter()
+                .copied()
+                .max_by_key(|&donor| (slots[donor], std::cmp::Reverse(donor)))
+                .expect("eligible is not empty");
+            slots[donor] -= 1;
+            slots[idx] += 1;
+        }
+    }
+
+    slots
+}
+
+/// Membagi tepat `cap` slot kepada target sesuai bobotnya sebagai rentang yang bersambung
+/// mulai dari slot 0 hingga `cap - 1` mengikuti urutan masukan, target tanpa slot dilewati
+pub fn allocate<T: Clone>(weights: &[(T, f64)], cap: u32) -> Vec<SlotRange<T>> {
+    let slots = slots(
+        &weights
+            .iter()
+            .map(|(_, weight)| *weight)
+            .collect::<Vec<_>>(),
+        cap,
+    );
+
+    let mut ranges = Vec::new();
+    let mut start = 0u32;
+    for ((target, _), slots) in weights.iter().zip(slots) {
+        if slots == 0 {
+            continue;
+        }
+        ranges.push(SlotRange {
+            start,
+            end: start + slots - 1,
+            target: target.clone(),
+        });
+        start += slots;
+    }
+    ranges
+}
@@ -0,0 +1,66 @@
+use serde_json::json;
+use tracing::{debug, info};
+
+use crate::{
+    actor::ServicePort, config::Config, nftables_backend::NftablesBackend,
+    update_nftables::service_chain,
+};
+
+/// Melepas pengendalian traffic sebuah port Service dengan menghapus elemen NodePort pada map
+/// verdict serta chain milik port tersebut, sehingga paket kembali diproses oleh kube-proxy
+pub async fn teardown_nftables(
+    config: Config,
+    backend: &dyn NftablesBackend,
+    service_key: String,
+    port: ServicePort,
+) -> anyhow::Result<()> {
+    let chain = service_chain(&config, &service_key, &port);
+    info!("actor: tearing down nftables routing of {chain}");
+
+    // elemen map harus dihapus terlebih dahulu karena chain yang masih
+    // dirujuk oleh verdict goto tidak dapat dihapus
+    let ruleset = json!({
+      "nftables": [
+        {
+          "delete": {
+            "element": {
+              "family": "inet",
+              "table": config.nftables.table,
+              "name": config.nftables.map_service_chain_by_nodeport,
+              "elem": [
+                {
+                  "concat": [
+                    port.protocol.as_str(),
+                    port.nodeport
+                  ]
+                }
+              ]
+            }
+          }
+        },
+        {
+          "flush": {
+            "chain": {
+              "family": "inet",
+              "table": config.nftables.table,
+              "name": chain
+            }
+          }
+        },
+        {
+          "delete": {
+            "chain": {
+              "family": "inet",
+              "table": config.nftables.table,
+              "name": chain
+            }
+          }
+        }
+      ]
+    });
+
+    debug!("actor: applying teardown ruleset: {ruleset}");
+    backend.apply(&ruleset)?;
+
+    Ok(())
+}
@@ -1,40 +1,81 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, collections::HashMap, net::IpAddr};
 
 use nftables::{
     batch::Batch,
     expr::{
-        Expression, Map, NamedExpression, NgMode, Numgen, Payload, PayloadField, Range, SetItem,
+        Expression, Map, Meta, MetaKey, NamedExpression, NgMode, Numgen, Payload, PayloadField,
+        Range, SetItem,
     },
-    helper,
     schema::{Chain, FlushObject, NfCmd, NfListObject, Rule},
     stmt::{Match, NAT, NATFamily, Operator, Statement},
     types::NfFamily,
 };
-use serde_json::json;
+use serde_json::{Value, json};
+use tokio::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
 use crate::{
-    actor::{ScorePair, Service},
-    config::Config,
+    actor::{Endpoint, EndpointDatapoint, ScorePair, Service, ServicePort},
+    config::{Config, DampeningConfig, EligibilityConfig, FallbackPolicy},
+    dampening::dampen,
     metrics,
+    nftables_backend::NftablesBackend,
+    scoring::{ScoringPolicy, scoring_policy},
+    slot_allocator,
 };
-fn performance_score(datapoint: &ScorePair, exponent: f64) -> f64 {
-    ((1.0 - datapoint.cpu) / datapoint.latency).powf(exponent)
+
+/// State pembagian traffic sebuah Service yang terakhir diterapkan
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceRuleset {
+    pub ruleset: Value,
+    // Node Name -> porsi traffic (0 hingga 1) yang diterapkan
+    pub share_by_nodename: HashMap<String, f64>,
+    // IP endpoint -> bagian endpoint (0 hingga 1) dari porsi node-nya pada family yang sama
+    pub split_by_endpoint: HashMap<IpAddr, f64>,
+}
+
+/// Hasil pembaruan aturan pembagian traffic sebuah Service
+#[derive(Clone, Debug, PartialEq)]
+pub enum RulesetUpdate {
+    /// State yang kini terpasang
+    Applied(ServiceRuleset),
+    /// Aturan tidak diterapkan sehingga ruleset sebelumnya (jika ada) tidak berubah
+    Skipped,
+    /// Kurang dari dua node yang layak dan Service harus dikembalikan kepada kube-proxy
+    Released,
+}
+
+impl RulesetUpdate {
+    /// State yang kini terpasang, `None` ketika aturan tidak diterapkan
+    pub fn applied(&self) -> Option<&ServiceRuleset> {
+        match self {
+            RulesetUpdate::Applied(applied) => Some(applied),
+            _ => None,
+        }
+    }
 }
 
+/// Menerapkan aturan pembagian traffic sebuah Service dalam satu transaksi nft. Transaksi
+/// dilewati ketika ruleset sama dengan `previous`. Node diluar jendela kelayakan Service tidak
+/// menerima traffic, kebijakan fallback berlaku ketika kurang dari dua node yang layak.
+/// Porsi sebuah node dibagi kepada pod-podnya sesuai datapoint masing-masing pada
+/// `datapoint_by_endpoint`, sinyal pod yang belum terukur memakai datapoint node
 pub async fn update_nftables(
     config: Config,
+    backend: &dyn NftablesBackend,
     mut service: Service,
     datapoint_by_nodename: HashMap<String, Option<ScorePair>>,
-) -> anyhow::Result<()> {
+    datapoint_by_endpoint: &HashMap<IpAddr, EndpointDatapoint>,
+    previous: Option<&ServiceRuleset>,
+) -> anyhow::Result<RulesetUpdate> {
     // skip nft update if service only has LESS THAN 2 node
     if service.endpoints_by_nodename.len() < 2 {
         info!(
             "actor: skipping ruleset application for service {} that only has {} nodes distribution",
-            service.name,
+            service.key(),
             service.endpoints_by_nodename.len()
         );
-        return Ok(());
+        return Ok(RulesetUpdate::Skipped);
     }
 
     info!("actor: starting to modify nftables for traffic routing");
@@ -42,206 +83,397 @@ pub async fn update_nftables(
         "actor: attempting to apply routing rulesets with args: {service:?}: {datapoint_by_nodename:?}"
     );
 
-    let chain = format!(
-        "{}-{}",
-        config.nftables.prefix_service_endpoint, service.name
-    );
-
-    let mut total_endpoints = 0;
-    let mut total_score = 0.0;
+    let service_key = service.key();
+    let eligibility = config
+        .service_eligibility
+        .get(&service_key)
+        .unwrap_or(&config.eligibility);
 
     // simpan daftar semua node sebelum filtering untuk keperluan metrik eligibility
     let all_nodes: Vec<String> = service.endpoints_by_nodename.keys().cloned().collect();
 
-    // filters out node with no datapoint and 0% cpu availability
-    service.endpoints_by_nodename.retain(|nodename, _| {
-        let Some(datapoint) = datapoint_by_nodename
+    // node tanpa datapoint (belum terukur atau di-eject) tidak pernah menerima traffic
+    let datapoint_of = |nodename: &str| {
+        datapoint_by_nodename
             .get(nodename)
             .and_then(|datapoint| datapoint.as_ref())
-        else {
-            return false;
-        };
-        datapoint.cpu > 0.0 && datapoint.cpu < 0.95
+    };
+    service
+        .endpoints_by_nodename
+        .retain(|nodename, _| datapoint_of(nodename).is_some());
+    let scored = service.endpoints_by_nodename.clone();
+
+    let now = Instant::now();
+    service.endpoints_by_nodename.retain(|nodename, _| {
+        datapoint_of(nodename).is_some_and(|datapoint| eligible(eligibility, datapoint, now))
     });
 
-    // count total endpoints and score
-    service
+    // skor node yang layak dapat seluruhnya bernilai 0 atau tidak valid, misalnya ketika
+    // seluruh pod mencapai limit CPU, sehingga porsi tidak dapat dibagi berdasarkan skor
+    let policy = scoring_policy(&config)?;
+    let eligible_datapoints: Vec<&ScorePair> = service
         .endpoints_by_nodename
-        .iter()
-        .for_each(|(nodename, endpoints)| {
-            let datapoint = datapoint_by_nodename
-                .get(nodename)
-                .and_then(|datapoint| datapoint.as_ref())
-                // this should be safe right, above code already filters out nodename with no datapoint
-                .unwrap();
+        .keys()
+        .filter_map(|nodename| datapoint_of(nodename))
+        .collect();
+    let total_score: f64 = policy.scores(&eligible_datapoints).iter().sum();
+    let scoreable = total_score.is_finite() && total_score > 0.0;
+
+    let fallback = service.endpoints_by_nodename.len() < 2 || !scoreable;
+    // porsi terakhir node yang masih hidup ketika kebijakan keepLastKnown berlaku
+    let mut kept_shares = None;
+    if fallback {
+        warn!(
+            "actor: {} nodes of service {service_key} are eligible with total score {total_score}, falling back to {:?}",
+            service.endpoints_by_nodename.len(),
+            eligibility.fallback,
+        );
+        match eligibility.fallback {
+            FallbackPolicy::EqualWeights => service.endpoints_by_nodename = scored,
+            FallbackPolicy::KeepLastKnown => {
+                let Some(previous) = previous else {
+                    return Ok(RulesetUpdate::Skipped);
+                };
+                // node yang di-eject, keluar dari cluster atau sinyalnya usang tidak lagi
+                // menerima porsi terakhirnya, node lain mempertahankan porsi masing-masing
+                let live: HashMap<String, f64> = previous
+                    .share_by_nodename
+                    .iter()
+                    .filter(|(nodename, _)| {
+                        scored.contains_key(*nodename)
+                            && datapoint_of(nodename)
+                                .is_some_and(|datapoint| fresh(eligibility, datapoint, now))
+                    })
+                    .map(|(nodename, share)| (nodename.clone(), *share))
+                    .collect();
+                if live.values().sum::<f64>() <= 0.0 {
+                    warn!(
+                        "actor: no node of the last known allocation of service {service_key} is alive, releasing"
+                    );
+                    return Ok(RulesetUpdate::Released);
+                }
+                service.endpoints_by_nodename = scored
+                    .into_iter()
+                    .filter(|(nodename, _)| live.contains_key(nodename))
+                    .collect();
+                kept_shares = Some(live);
+            }
+            FallbackPolicy::KubeProxy => return Ok(RulesetUpdate::Released),
+        }
+    }
 
-            let score = performance_score(datapoint, config.alpha.score_exponent);
-            total_endpoints += endpoints.len();
-            total_score += score;
-        });
+    let total_endpoints: usize = service.endpoints_by_nodename.values().map(Vec::len).sum();
 
-    if total_endpoints < 2 {
+    // Service yang belum dikendalikan tidak diarahkan seluruhnya kepada satu endpoint, namun
+    // Service yang telah dikendalikan tetap diperbarui agar traffic tidak terus diarahkan
+    // kepada node yang telah di-eject, termasuk ketika fallback keepLastKnown berlaku
+    if total_endpoints == 0 || (total_endpoints < 2 && previous.is_none()) {
         warn!(
             "actor: skipping distributed service {} with only {total_endpoints} endpoints",
-            service.name,
+            service.key(),
         );
-        return Ok(());
+        return Ok(RulesetUpdate::Skipped);
     }
 
-    let mut verdict_pairs = Vec::<SetItem>::new();
-    let mut starting = 0u32;
     let probability_cap = config.nftables.probability_cap;
     let mut score_by_nodename = HashMap::new();
 
     // menulis probability_cap ke metrik Prometheus
-    metrics::set_probability_cap(&service.name, probability_cap);
+    metrics::set_probability_cap(&service_key, probability_cap);
 
     // set eligible=0 untuk semua node yang ter-filter sebelum scoring
     for node in &all_nodes {
         if !service.endpoints_by_nodename.contains_key(node) {
-            metrics::set_node_eligible(node, &service.name, 0.0);
-            metrics::set_performance_score(node, &service.name, 0.0);
-            metrics::set_score_percentage(node, &service.name, 0.0);
-            metrics::set_nft_slots(node, &service.name, 0);
+            metrics::set_node_eligible(node, &service_key, 0.0);
+            metrics::set_performance_score(node, &service_key, 0.0);
+            metrics::set_score_percentage(node, &service_key, 0.0);
+            metrics::set_nft_slots(node, &service_key, 0);
         }
     }
 
-    service
-        .endpoints_by_nodename
+    // node diurutkan berdasarkan nama agar rentang slot yang dihasilkan selalu sama
+    // untuk masukan yang sama
+    let mut endpoints_by_nodename: Vec<_> = service.endpoints_by_nodename.iter().collect();
+    endpoints_by_nodename.sort_by_key(|(nodename, _)| *nodename);
+
+    let datapoints: Vec<&ScorePair> = endpoints_by_nodename
         .iter()
-        .for_each(|(nodename, endpoints)| {
-            let datapoint = datapoint_by_nodename
-                .get(nodename)
+        .map(|(nodename, _)| {
+            datapoint_by_nodename
+                .get(*nodename)
                 .and_then(|datapoint| datapoint.as_ref())
                 // this should be safe right, above code already filters out nodename with no datapoint
-                .unwrap();
-
-            let score = performance_score(datapoint, config.alpha.score_exponent);
-            let score_percentage = score / total_score;
-
-            score_by_nodename.insert(nodename.clone(), score_percentage * 100.0);
-            let node_portion = (score_percentage * probability_cap as f64).round() as u32;
-
-            // menulis metrik performance score dan score percentage ke Prometheus
-            metrics::set_performance_score(nodename, &service.name, score);
-            metrics::set_score_percentage(nodename, &service.name, score_percentage * 100.0);
-
-            if node_portion == 0 {
-                warn!("actor: node {} got 0 portion, skipping", nodename);
-                metrics::set_nft_slots(nodename, &service.name, 0);
-                metrics::set_node_eligible(nodename, &service.name, 0.0);
-                return;
-            }
-
-            // node eligible: lolos filter dan mendapat porsi > 0
-            metrics::set_nft_slots(nodename, &service.name, node_portion);
-            metrics::set_node_eligible(nodename, &service.name, 1.0);
-
-            // Distribute evenly across endpoints, using floor to stay within bounds
-            let portion_each = node_portion / endpoints.len() as u32;
-            let remainder = node_portion % endpoints.len() as u32;
-
-            if portion_each == 0 {
-                warn!(
-                    "actor: portion_each is 0 for node {} with {} endpoints",
-                    nodename,
-                    endpoints.len()
-                );
-                return;
-            }
-
-            for (idx, endpoint) in endpoints.iter().enumerate() {
-                // Give remainder to first few endpoints
-                let this_portion = if idx < remainder as usize {
-                    portion_each + 1
-                } else {
-                    portion_each
-                };
-
-                // Safety check: don't exceed probability_cap
-                if starting >= probability_cap {
-                    warn!(
-                        "actor: reached probability_cap limit, stopping at {}",
-                        starting
-                    );
-                    return;
-                }
+                .unwrap()
+        })
+        .collect();
+    // fallback membagi porsi secara rata tanpa memperhatikan skor, sedangkan keepLastKnown
+    // memakai porsi terakhir sebagai skor
+    let scores = match &kept_shares {
+        Some(kept_shares) => endpoints_by_nodename
+            .iter()
+            .map(|(nodename, _)| kept_shares[*nodename])
+            .collect(),
+        None if fallback => vec![1.0; datapoints.len()],
+        None => policy.scores(&datapoints),
+    };
+    let total_score: f64 = scores.iter().sum();
+    let target: HashMap<String, f64> = endpoints_by_nodename
+        .iter()
+        .zip(&scores)
+        .map(|((nodename, _), score)| ((*nodename).clone(), score / total_score))
+        .collect();
+
+    // porsi target diredam terhadap porsi yang terakhir diterapkan agar
+    // sampel EWMA yang fluktuatif tidak memindahkan traffic secara drastis,
+    // porsi terakhir yang dipertahankan cukup dinormalisasi ulang
+    let share_by_nodename = if kept_shares.is_some() {
+        target
+    } else {
+        dampen(
+            previous.map(|previous| &previous.share_by_nodename),
+            target,
+            &config.dampening,
+        )
+    };
+    let shares: Vec<f64> = endpoints_by_nodename
+        .iter()
+        .map(|(nodename, _)| share_by_nodename[*nodename])
+        .collect();
+    let node_slots = slot_allocator::slots(&shares, probability_cap);
+
+    let mut portions = Vec::<(f64, Option<&ScorePair>, &Vec<Endpoint>)>::new();
+    for (((((nodename, endpoints), datapoint), score), share), slots) in endpoints_by_nodename
+        .into_iter()
+        .zip(datapoints)
+        .zip(scores)
+        .zip(shares)
+        .zip(node_slots)
+    {
+        let score_percentage = score / total_score;
+        score_by_nodename.insert(nodename.clone(), score_percentage * 100.0);
+
+        // menulis metrik performance score dan score percentage ke Prometheus
+        metrics::set_performance_score(nodename, &service_key, score);
+        metrics::set_score_percentage(nodename, &service_key, score_percentage * 100.0);
+        metrics::set_nft_slots(nodename, &service_key, slots);
+
+        if slots == 0 {
+            warn!("actor: node {} got 0 portion, skipping", nodename);
+            metrics::set_node_eligible(nodename, &service_key, 0.0);
+            continue;
+        }
 
-                let end = (starting + this_portion - 1).min(probability_cap - 1);
+        // node eligible: lolos filter dan mendapat porsi > 0
+        metrics::set_node_eligible(nodename, &service_key, 1.0);
+        portions.push((
+            share,
+            (!fallback || kept_shares.is_some()).then_some(datapoint),
+            endpoints,
+        ));
+    }
+    info!("actor: {service_key} node scores: {score_by_nodename:?}");
+
+    // seluruh slot dibagikan secara terpisah kepada endpoint IPv4 dan IPv6, masing-masing
+    // family memiliki aturan DNAT sendiri pada chain Service. Porsi node dibagi kepada
+    // endpoint node tersebut sesuai skornya sehingga setiap endpoint memperoleh slot sesuai bobotnya
+    let mut allocations = Vec::new();
+    let mut split_by_endpoint = HashMap::new();
+    for family in [NATFamily::IP, NATFamily::IP6] {
+        let weights = endpoint_weights(
+            policy.as_ref(),
+            &portions,
+            datapoint_by_endpoint,
+            family,
+            previous.map(|previous| &previous.split_by_endpoint),
+            &config.dampening,
+            &mut split_by_endpoint,
+        );
+        let ranges = slot_allocator::allocate(&weights, probability_cap);
+        if ranges.is_empty() {
+            continue;
+        }
 
-                verdict_pairs.push(SetItem::Mapping(
+        debug!(
+            "actor: generated {} {family:?} verdict pairs over {probability_cap} slots",
+            ranges.len(),
+        );
+        let verdict_pairs = ranges
+            .into_iter()
+            .map(|range| {
+                SetItem::Mapping(
                     Expression::Range(
                         Range {
-                            range: [Expression::Number(starting), Expression::Number(end)],
+                            range: [
+                                Expression::Number(range.start),
+                                Expression::Number(range.end),
+                            ],
                         }
                         .into(),
                     ),
-                    Expression::String(endpoint.to_string().into()),
-                ));
-                starting = end + 1;
-
-                if starting >= probability_cap {
-                    break;
-                }
-            }
-        });
-    info!("actor: {chain} node scores: {score_by_nodename:?}");
+                    Expression::String(range.target.to_string().into()),
+                )
+            })
+            .collect::<Vec<_>>();
+        // numgen random mod N menghasilkan slot 0 hingga N - 1
+        allocations.push((family, verdict_pairs, probability_cap));
+    }
 
-    if verdict_pairs.is_empty() {
+    if allocations.is_empty() {
         warn!(
             "actor: no verdict pairs generated for service {}, skipping",
-            service.name
+            service.key()
         );
-        return Ok(());
+        return Ok(RulesetUpdate::Skipped);
     }
 
-    let ng_mod_value = if starting > 0 {
-        starting - 1
-    } else {
-        probability_cap - 1
+    // setiap port Service dikendalikan secara terpisah melalui chain miliknya sendiri
+    // dengan pembagian slot yang sama, seluruh perintah seluruh port digabungkan dalam
+    // satu transaksi nft sehingga paket tidak pernah melewati chain yang sedang kosong
+    let mut commands = Vec::new();
+    for port in &service.ports {
+        let chain = service_chain(&config, &service_key, port);
+
+        let mut batch = Batch::new();
+        batch.add(NfListObject::Chain(Chain {
+            family: NfFamily::INet,
+            table: config.nftables.table.clone().into(),
+            name: chain.clone().into(),
+            ..Default::default()
+        }));
+        batch.add_cmd(NfCmd::Flush(FlushObject::Chain(Chain {
+            family: NfFamily::INet,
+            table: config.nftables.table.clone().into(),
+            name: chain.clone().into(),
+            ..Default::default()
+        })));
+        for (family, verdict_pairs, ng_mod_value) in &allocations {
+            batch.add(NfListObject::Rule(dnat_rule(
+                &config,
+                &chain,
+                port,
+                *family,
+                verdict_pairs.clone(),
+                *ng_mod_value,
+            )));
+        }
+        for object in batch.to_nftables().objects.iter() {
+            commands.push(serde_json::to_value(object)?);
+        }
+
+        commands.push(json!({
+          "add": {
+            "element": {
+              "family": "inet",
+              "table": config.nftables.table,
+              "name": config.nftables.map_service_chain_by_nodeport,
+              "elem": [
+                [
+                  {
+                    "concat": [
+                      port.protocol.as_str(),
+                      port.nodeport
+                    ]
+                  },
+                  {
+                    "goto": {
+                      "target": chain
+                    }
+                  }
+                ]
+              ]
+            }
+          }
+        }));
+    }
+    let applied = ServiceRuleset {
+        ruleset: json!({ "nftables": commands }),
+        share_by_nodename,
+        split_by_endpoint,
     };
 
+    // state yang sama dengan penerapan terakhir tidak perlu dikirim ulang kepada nft
+    if previous.is_some_and(|previous| previous.ruleset == applied.ruleset) {
+        debug!("actor: ruleset of service {service_key} unchanged, skipping nft transaction");
+        return Ok(RulesetUpdate::Applied(applied));
+    }
+
     debug!(
-        "actor: generated {} verdict pairs, range coverage: [0, {}], ng_mod: {}",
-        verdict_pairs.len(),
-        starting - 1,
-        ng_mod_value
+        "actor: applying service routing ruleset: {}",
+        applied.ruleset
     );
+    backend.apply(&applied.ruleset)?;
 
-    // try create service chain first, if already exist just error silently
-    let mut batch = Batch::new();
-    batch.add(NfListObject::Chain(Chain {
-        family: NfFamily::IP,
-        table: config.nftables.table.clone().into(),
-        name: chain.clone().into(),
-        ..Default::default()
-    }));
-    helper::apply_ruleset(&batch.to_nftables())?;
+    Ok(RulesetUpdate::Applied(applied))
+}
 
-    let mut batch = Batch::new();
-    batch.add_cmd(NfCmd::Flush(FlushObject::Chain(Chain {
-        family: NfFamily::IP,
-        table: config.nftables.table.clone().into(),
-        name: chain.clone().into(),
-        ..Default::default()
-    })));
-    batch.add(NfListObject::Rule(Rule {
-        family: NfFamily::IP,
+/// Apakah seluruh sinyal node berada dibawah batas pada jendela kelayakan Service
+fn eligible(eligibility: &EligibilityConfig, datapoint: &ScorePair, now: Instant) -> bool {
+    let within = |limit: Option<f64>, value: f64| limit.is_none_or(|limit| value < limit);
+    within(eligibility.max_latency_ms, datapoint.latency)
+        && within(eligibility.max_cpu, datapoint.cpu)
+        && within(eligibility.max_memory, datapoint.memory)
+        && within(eligibility.max_network, datapoint.network)
+        && within(eligibility.max_pod_cpu, datapoint.pod_cpu)
+        && within(eligibility.max_rtt_ms, datapoint.rtt)
+        && fresh(eligibility, datapoint, now)
+}
+
+/// Apakah sinyal node tidak lebih tua dari `maxStaleness` Service
+fn fresh(eligibility: &EligibilityConfig, datapoint: &ScorePair, now: Instant) -> bool {
+    eligibility.max_staleness.is_none_or(|max_staleness| {
+        datapoint.updated_at.is_none_or(|updated_at| {
+            now.saturating_duration_since(updated_at) <= Duration::from_secs(max_staleness)
+        })
+    })
+}
+
+/// Nama chain milik sebuah port Service dengan format <prefix>-<namespace>/<nama>/<protokol>/<NodePort>
+pub fn service_chain(config: &Config, service_key: &str, port: &ServicePort) -> String {
+    format!(
+        "{}-{}/{}/{}",
+        config.nftables.prefix_service_endpoint,
+        service_key,
+        port.protocol.as_str(),
+        port.nodeport
+    )
+}
+
+/// Aturan DNAT sebuah port Service untuk satu family alamat
+fn dnat_rule<'a>(
+    config: &Config,
+    chain: &str,
+    port: &ServicePort,
+    family: NATFamily,
+    verdict_pairs: Vec<SetItem<'a>>,
+    ng_mod_value: u32,
+) -> Rule<'a> {
+    Rule {
+        family: NfFamily::INet,
         table: config.nftables.table.clone().into(),
-        chain: chain.clone().into(),
+        chain: chain.to_string().into(),
         expr: Cow::Owned(vec![
+            Statement::Match(Match {
+                left: Expression::Named(NamedExpression::Meta(Meta {
+                    key: MetaKey::Nfproto,
+                })),
+                right: Expression::String(Cow::Borrowed(match family {
+                    NATFamily::IP => "ipv4",
+                    NATFamily::IP6 => "ipv6",
+                })),
+                op: Operator::EQ,
+            }),
             Statement::Match(Match {
                 left: Expression::Named(NamedExpression::Payload(Payload::PayloadField(
                     PayloadField {
-                        protocol: Cow::Borrowed("tcp"),
+                        protocol: Cow::Borrowed(port.protocol.as_str()),
                         field: Cow::Borrowed("dport"),
                     },
                 ))),
-                right: Expression::Number(service.nodeport as u32),
+                right: Expression::Number(port.nodeport as u32),
                 op: Operator::EQ,
             }),
             Statement::DNAT(Some(NAT {
-                family: NATFamily::IP.into(),
+                family: family.into(),
                 addr: Expression::Named(NamedExpression::Map(Box::new(Map {
                     key: Expression::Named(NamedExpression::Numgen(Numgen {
                         mode: NgMode::Random,
@@ -251,56 +483,90 @@ pub async fn update_nftables(
                     data: Expression::Named(NamedExpression::Set(verdict_pairs)),
                 })))
                 .into(),
-                port: Some(Expression::Number(service.targetport as u32)),
+                port: Some(Expression::Number(port.targetport as u32)),
                 flags: None,
             })),
         ]),
         comment: Some(format!("Load balancing for service {}", chain).into()),
         handle: Some(0),
         ..Default::default()
-    }));
+    }
+}
 
-    let ruleset = batch.to_nftables();
-    debug!(
-        "actor: attaching anonymous map routing rule: {}",
-        serde_json::to_string(&ruleset)?
-    );
-    helper::apply_ruleset(&ruleset)?;
-
-    helper::apply_ruleset_raw(
-        json!({
-          "nftables": [
-            {
-              "add": {
-                "element": {
-                  "family": "ip",
-                  "table": config.nftables.table,
-                  "name": config.nftables.map_service_chain_by_nodeport,
-                  "elem": [
-                    [
-                      {
-                        "concat": [
-                          "tcp",
-                          service.nodeport
-                        ]
-                      },
-                      {
-                        "goto": {
-                          "target": chain
+/// Bobot setiap endpoint dengan family alamat sesuai, porsi node dibagi kepada endpoint node
+/// tersebut sebanding dengan skor masing-masing. Skor endpoint dihitung dengan kebijakan
+/// scoring yang sama dari datapoint node dengan latency dan CPU pod endpoint tersebut,
+/// porsi node tanpa datapoint dibagi rata. Pembagian diredam terhadap `previous` seperti
+/// porsi node dan hasilnya dicatat pada `split_by_endpoint`
+fn endpoint_weights(
+    policy: &dyn ScoringPolicy,
+    portions: &[(f64, Option<&ScorePair>, &Vec<Endpoint>)],
+    datapoint_by_endpoint: &HashMap<IpAddr, EndpointDatapoint>,
+    family: NATFamily,
+    previous: Option<&HashMap<IpAddr, f64>>,
+    dampening: &DampeningConfig,
+    split_by_endpoint: &mut HashMap<IpAddr, f64>,
+) -> Vec<(IpAddr, f64)> {
+    let mut weights = Vec::new();
+    for (share, datapoint, endpoints) in portions {
+        let endpoints: Vec<&Endpoint> = endpoints
+            .iter()
+            .filter(|endpoint| match family {
+                NATFamily::IP => endpoint.ip.is_ipv4(),
+                NATFamily::IP6 => endpoint.ip.is_ipv6(),
+            })
+            .collect();
+        let scores = match datapoint {
+            Some(datapoint) => {
+                let datapoints: Vec<ScorePair> = endpoints
+                    .iter()
+                    .map(|endpoint| {
+                        let endpoint = datapoint_by_endpoint.get(&endpoint.ip);
+                        ScorePair {
+                            latency: endpoint
+                                .and_then(|endpoint| endpoint.latency)
+                                .unwrap_or(datapoint.latency),
+                            pod_cpu: endpoint
+                                .and_then(|endpoint| endpoint.pod_cpu)
+                                .unwrap_or(datapoint.pod_cpu),
+                            ..(*datapoint).clone()
                         }
-                      }
-                    ]
-                  ]
-                }
-              }
+                    })
+                    .collect();
+                policy.scores(&datapoints.iter().collect::<Vec<_>>())
             }
-          ]
-        })
-        .to_string()
-        .as_ref(),
-        None::<&str>,
-        std::iter::empty::<&str>(),
-    )?;
-
-    Ok(())
+            None => vec![1.0; endpoints.len()],
+        };
+        let total_score: f64 = scores.iter().sum();
+        let target: HashMap<IpAddr, f64> = endpoints
+            .iter()
+            .zip(scores)
+            .map(|(endpoint, score)| {
+                // skor yang tidak valid kembali dibagi rata agar node tetap menerima porsinya
+                let split = if total_score.is_finite() && total_score > 0.0 {
+                    score / total_score
+                } else {
+                    1.0 / endpoints.len() as f64
+                };
+                (endpoint.ip, split)
+            })
+            .collect();
+
+        // jitter latency antar pod pada node yang sama tidak boleh menggeser rentang slot
+        // setiap tick, sehingga pembagian diredam terhadap pembagian terakhir endpoint node ini
+        let previous: Option<HashMap<IpAddr, f64>> = previous
+            .map(|previous| {
+                endpoints
+                    .iter()
+                    .filter_map(|endpoint| Some((endpoint.ip, *previous.get(&endpoint.ip)?)))
+                    .collect::<HashMap<_, _>>()
+            })
+            .filter(|previous| !previous.is_empty());
+        let split = dampen(previous.as_ref(), target, dampening);
+        for endpoint in &endpoints {
+            weights.push((endpoint.ip, share * split[&endpoint.ip]));
+        }
+        split_by_endpoint.extend(split);
+    }
+    weights
 }
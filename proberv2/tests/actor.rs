@@ -0,0 +1,285 @@
+mod common;
+
+use std::sync::Arc;
+
+use proberv2::{
+    actor::{Actor, Event, EwmaDatapoint, WorkerNode, try_next_event},
+    config::{Config, FallbackPolicy},
+    nftables_backend::FakeNftables,
+};
+
+fn actor() -> (Actor, Arc<FakeNftables>) {
+    actor_with(common::config())
+}
+
+fn actor_with(config: Config) -> (Actor, Arc<FakeNftables>) {
+    let backend = Arc::new(FakeNftables::new());
+    (Actor::new(config, backend.clone()), backend)
+}
+
+async fn join(actor: &mut Actor, nodename: &str, ip: &str, cpu: f64, latency: f64) {
+    let worker = WorkerNode {
+        name: nodename.to_string(),
+        ip: ip.parse().unwrap(),
+        hostname: nodename.to_string(),
+    };
+    actor.handle(Event::NodeJoined(worker)).await;
+    actor
+        .handle(Event::EwmaCalculated(
+            nodename.to_string(),
+            EwmaDatapoint::Cpu(cpu),
+        ))
+        .await;
+    actor
+        .handle(Event::ServiceEwmaCalculated(
+            "default/web".to_string(),
+            nodename.to_string(),
+            EwmaDatapoint::Latency(latency),
+        ))
+        .await;
+}
+
+/// Perintah nftables pertama pada setiap ruleset, misalnya "add" atau "delete"
+fn commands(rulesets: &[serde_json::Value]) -> Vec<String> {
+    rulesets
+        .iter()
+        .map(|ruleset| {
+            let command = &ruleset["nftables"][0];
+            let (verb, object) = command.as_object().unwrap().iter().next().unwrap();
+            let object = object.as_object().unwrap().keys().next().unwrap();
+            format!("{verb} {object}")
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn steers_service_once_nodes_are_scored() {
+    let (mut actor, backend) = actor();
+    join(&mut actor, "node-a", "192.168.0.1", 0.2, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    actor.handle(Event::ServiceChanged(service)).await;
+
+    assert_eq!(commands(&backend.take()), ["add chain"]);
+    assert!(actor.steered_ports.contains_key("default/web"));
+
+    // bobot yang tidak berubah tidak memicu transaksi nft baru
+    actor.tick().await;
+    assert!(backend.applied().is_empty());
+}
+
+#[tokio::test]
+async fn reapplies_service_after_release() {
+    let (mut actor, backend) = actor();
+    join(&mut actor, "node-a", "192.168.0.1", 0.2, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    actor.handle(Event::ServiceChanged(service.clone())).await;
+    actor
+        .handle(Event::ServiceChanged(common::service(&[(
+            "node-a",
+            &["10.0.0.1"],
+        )])))
+        .await;
+    backend.take();
+
+    // latency node yang sempat tidak menjalankan endpoint diukur ulang oleh probe
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+    actor.handle(Event::ServiceChanged(service)).await;
+
+    assert_eq!(commands(&backend.take()), ["add chain"]);
+}
+
+#[tokio::test]
+async fn releases_service_when_endpoints_shrink_to_one_node() {
+    let (mut actor, backend) = actor();
+    join(&mut actor, "node-a", "192.168.0.1", 0.2, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    actor.handle(Event::ServiceChanged(service)).await;
+    backend.take();
+
+    let service = common::service(&[("node-a", &["10.0.0.1"])]);
+    actor.handle(Event::ServiceChanged(service)).await;
+
+    assert_eq!(commands(&backend.take()), ["delete element"]);
+    assert!(!actor.steered_ports.contains_key("default/web"));
+
+    // tick berikutnya tidak lagi menyentuh ruleset Service yang telah dilepas
+    actor.tick().await;
+    assert!(backend.applied().is_empty());
+}
+
+#[tokio::test]
+async fn releases_removed_service() {
+    let (mut actor, backend) = actor();
+    join(&mut actor, "node-a", "192.168.0.1", 0.2, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    actor.handle(Event::ServiceChanged(service.clone())).await;
+    backend.take();
+
+    actor.handle(Event::ServiceRemoved(service)).await;
+
+    assert_eq!(commands(&backend.take()), ["delete element"]);
+    assert!(actor.service_by_key.is_empty());
+}
+
+#[tokio::test]
+async fn departed_node_loses_its_traffic_share() {
+    let (mut actor, backend) = actor();
+    join(&mut actor, "node-a", "192.168.0.1", 0.2, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+    join(&mut actor, "node-c", "192.168.0.3", 0.4, 10.0).await;
+    let service = common::service(&[
+        ("node-a", &["10.0.0.1"]),
+        ("node-b", &["10.0.0.2"]),
+        ("node-c", &["10.0.0.3"]),
+    ]);
+    actor.handle(Event::ServiceChanged(service)).await;
+    backend.take();
+
+    let worker = WorkerNode {
+        name: "node-c".to_string(),
+        ip: "192.168.0.3".parse().unwrap(),
+        hostname: "node-c".to_string(),
+    };
+    actor.handle(Event::NodeLeft(worker)).await;
+    actor.tick().await;
+
+    let rulesets = backend.take();
+    let rule = serde_json::to_string(&rulesets[0]).unwrap();
+    assert!(rule.contains("10.0.0.1") && rule.contains("10.0.0.2"));
+    assert!(!rule.contains("10.0.0.3"));
+}
+
+#[tokio::test]
+async fn ejected_node_loses_its_traffic_share_until_readmitted() {
+    let (mut actor, backend) = actor();
+    join(&mut actor, "node-a", "192.168.0.1", 0.2, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    actor.handle(Event::ServiceChanged(service)).await;
+    backend.take();
+
+    actor
+        .handle(Event::NodeEjected(
+            "default/web".to_string(),
+            "node-b".to_string(),
+        ))
+        .await;
+
+    let rule = serde_json::to_string(&backend.take()[0]).unwrap();
+    assert!(rule.contains("10.0.0.1") && !rule.contains("10.0.0.2"));
+
+    actor
+        .handle(Event::NodeReadmitted(
+            "default/web".to_string(),
+            "node-b".to_string(),
+        ))
+        .await;
+    actor.tick().await;
+
+    let rule = serde_json::to_string(&backend.take()[0]).unwrap();
+    assert!(rule.contains("10.0.0.1") && rule.contains("10.0.0.2"));
+}
+
+#[tokio::test]
+async fn updated_node_is_unsteered_until_measured_again() {
+    let (mut actor, backend) = actor();
+    join(&mut actor, "node-a", "192.168.0.1", 0.2, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    actor.handle(Event::ServiceChanged(service)).await;
+    backend.take();
+
+    // node-b diganti dengan instance beralamat baru
+    actor
+        .handle(Event::NodeUpdated(WorkerNode {
+            name: "node-b".to_string(),
+            ip: "192.168.0.20".parse().unwrap(),
+            hostname: "node-b".to_string(),
+        }))
+        .await;
+    assert!(actor.datapoint_by_nodename["node-b"].is_none());
+    assert!(!actor.latency_by_service["default/web"].contains_key("node-b"));
+    actor.tick().await;
+
+    let rule = serde_json::to_string(&backend.take()[0]).unwrap();
+    assert!(rule.contains("10.0.0.1") && !rule.contains("10.0.0.2"));
+
+    join(&mut actor, "node-b", "192.168.0.20", 0.4, 10.0).await;
+    actor.tick().await;
+
+    let rule = serde_json::to_string(&backend.take()[0]).unwrap();
+    assert!(rule.contains("10.0.0.1") && rule.contains("10.0.0.2"));
+}
+
+#[tokio::test]
+async fn ejection_overrides_last_known_allocation() {
+    let (mut actor, backend) = actor();
+    join(&mut actor, "node-a", "192.168.0.1", 0.2, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+    join(&mut actor, "node-c", "192.168.0.3", 0.3, 10.0).await;
+    let service = common::service(&[
+        ("node-a", &["10.0.0.1"]),
+        ("node-b", &["10.0.0.2"]),
+        ("node-c", &["10.0.0.3"]),
+    ]);
+    actor.handle(Event::ServiceChanged(service)).await;
+    backend.take();
+
+    // node-a dan node-b melewati batas CPU sehingga bobot terakhir dipertahankan
+    join(&mut actor, "node-a", "192.168.0.1", 0.97, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.98, 10.0).await;
+    actor.tick().await;
+    assert!(backend.applied().is_empty());
+
+    actor
+        .handle(Event::NodeEjected(
+            "default/web".to_string(),
+            "node-c".to_string(),
+        ))
+        .await;
+
+    let rule = serde_json::to_string(&backend.take()[0]).unwrap();
+    assert!(rule.contains("10.0.0.1") && rule.contains("10.0.0.2"));
+    assert!(!rule.contains("10.0.0.3"));
+}
+
+#[tokio::test]
+async fn releases_service_to_kube_proxy_when_nodes_become_ineligible() {
+    let mut config = common::config();
+    config.eligibility.fallback = FallbackPolicy::KubeProxy;
+    let (mut actor, backend) = actor_with(config);
+    join(&mut actor, "node-a", "192.168.0.1", 0.2, 5.0).await;
+    join(&mut actor, "node-b", "192.168.0.2", 0.4, 10.0).await;
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    actor.handle(Event::ServiceChanged(service)).await;
+    backend.take();
+
+    join(&mut actor, "node-b", "192.168.0.2", 0.98, 10.0).await;
+    actor.tick().await;
+
+    assert_eq!(commands(&backend.take()), ["delete element"]);
+    assert!(!actor.steered_ports.contains_key("default/web"));
+}
+
+#[test]
+fn lagged_receiver_keeps_draining_remaining_events() {
+    let (tx, mut rx) = tokio::sync::broadcast::channel(2);
+    for nodename in ["node-a", "node-b", "node-c"] {
+        let event = Event::EwmaCalculated(nodename.to_string(), EwmaDatapoint::Cpu(0.5));
+        assert!(tx.send(event).is_ok());
+    }
+
+    let mut drained = Vec::new();
+    while let Some(event) = try_next_event(&mut rx) {
+        if let Event::EwmaCalculated(nodename, _) = event {
+            drained.push(nodename);
+        }
+    }
+
+    assert_eq!(drained, ["node-b", "node-c"]);
+}
@@ -0,0 +1,162 @@
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use axum::{Json, Router, extract::Query, routing::get};
+use proberv2::{
+    actor::{Endpoint, Protocol, ScorePair, Service, ServicePort, WorkerNode},
+    config::Config,
+};
+use tokio::net::TcpListener;
+
+pub fn config() -> Config {
+    serde_json::from_value(serde_json::json!({
+        "shutdownTimeout": 1,
+        "kubernetes": {},
+        "prometheus": { "url": "http://127.0.0.1:9090" },
+        "nftables": {
+            "table": "proberv2",
+            "chainPrerouting": "prerouting",
+            "chainServices": "services",
+            "setAllowedNodeIps": "nodeport-ips",
+            "mapServiceChainByNodeport": "service-verdict-by-nodeport",
+            "prefixServiceEndpoint": "lb",
+            "probabilityCap": 100
+        },
+        "probe": {
+            "latencyInterval": 10,
+            "cpuInterval": 10,
+            "nftUpdateInterval": 10
+        },
+        "alpha": {
+            "ewmaLatency": 0.2,
+            "ewmaCpu": 0.2,
+            "scoreExponent": 1.0
+        }
+    }))
+    .unwrap()
+}
+
+/// Menjalankan router sebagai server stand-in (Prometheus, API server) pada port acak
+pub async fn serve(app: Router) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    address
+}
+
+/// Server Prometheus stand-in yang menjawab setiap instant query dengan vector hasil
+/// `series(query)`, query yang diterima dicatat untuk diperiksa
+pub async fn prometheus(
+    series: impl Fn(&str) -> serde_json::Value + Clone + Send + Sync + 'static,
+) -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+    let queries = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new().route(
+        "/api/v1/query",
+        get({
+            let queries = queries.clone();
+            move |Query(params): Query<HashMap<String, String>>| async move {
+                let query = params["query"].clone();
+                queries.lock().unwrap().push(query.clone());
+                Json(serde_json::json!({
+                    "status": "success",
+                    "data": { "resultType": "vector", "result": series(&query) }
+                }))
+            }
+        }),
+    );
+    (serve(app).await, queries)
+}
+
+/// Client Prometheus yang terhubung dengan server stand-in
+pub fn prometheus_client(address: SocketAddr) -> prometheus_http_query::Client {
+    prometheus_http_query::Client::try_from(format!("http://{address}")).unwrap()
+}
+
+/// Client Kubernetes yang terhubung dengan server stand-in
+pub fn kube_client(address: SocketAddr) -> kube::Client {
+    let config = kube::Config::new(format!("http://{address}").parse().unwrap());
+    kube::Client::try_from(config).unwrap()
+}
+
+/// Worker node-a dengan alamat IPv4 dan node-b dengan alamat IPv6
+pub fn workers() -> Vec<WorkerNode> {
+    vec![
+        WorkerNode {
+            name: "node-a".to_string(),
+            ip: "10.0.0.1".parse().unwrap(),
+            hostname: "node-a.lan".to_string(),
+        },
+        WorkerNode {
+            name: "node-b".to_string(),
+            ip: "fd00::2".parse().unwrap(),
+            hostname: "node-b.lan".to_string(),
+        },
+    ]
+}
+
+pub fn endpoint(ip: &str) -> Endpoint {
+    Endpoint {
+        ip: ip.parse().unwrap(),
+        pod: None,
+        zone: None,
+    }
+}
+
+/// Service default/web dengan NodePort tcp 30000 -> 8080
+pub fn service(endpoints_by_nodename: &[(&str, &[&str])]) -> Service {
+    Service {
+        namespace: "default".to_string(),
+        name: "web".to_string(),
+        ports: vec![ServicePort {
+            protocol: Protocol::Tcp,
+            nodeport: 30000,
+            targetport: 8080,
+        }],
+        endpoints_by_nodename: endpoints_by_nodename
+            .iter()
+            .map(|(nodename, ips)| {
+                (
+                    nodename.to_string(),
+                    ips.iter().map(|ip| endpoint(ip)).collect(),
+                )
+            })
+            .collect(),
+    }
+}
+
+pub fn datapoints(scores: &[(&str, f64, f64)]) -> HashMap<String, Option<ScorePair>> {
+    scores
+        .iter()
+        .map(|(nodename, latency, cpu)| {
+            (
+                nodename.to_string(),
+                Some(ScorePair {
+                    latency: *latency,
+                    cpu: *cpu,
+                    ..Default::default()
+                }),
+            )
+        })
+        .collect()
+}
+
+/// Membandingkan ruleset dengan file golden pada tests/golden, jalankan dengan
+/// `UPDATE_GOLDEN=1` untuk menulis ulang file golden dari hasil saat ini
+pub fn assert_golden(name: &str, actual: &[serde_json::Value]) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.json"));
+    let actual = serde_json::Value::Array(actual.to_vec());
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+        return;
+    }
+    let expected: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(actual, expected, "ruleset differs from {}", path.display());
+}
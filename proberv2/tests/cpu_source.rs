@@ -0,0 +1,175 @@
+mod common;
+
+use std::{collections::HashMap, net::SocketAddr};
+
+use axum::{Json, Router, extract::Path, routing::get};
+use proberv2::{
+    config::PrometheusJoin,
+    cpu_source::{CpuSource, KubeletSummary, MetricsApi, ProcStat, Prometheus, parse_cpu_quantity},
+};
+use serde_json::json;
+
+/// Daftar Node dengan kapasitas CPU node-a 4 core dan node-b 2 core
+fn node_list() -> Router {
+    Router::new().route(
+        "/api/v1/nodes",
+        get(|| async {
+            Json(json!({
+                "apiVersion": "v1",
+                "kind": "NodeList",
+                "metadata": {},
+                "items": [
+                    { "metadata": { "name": "node-a" }, "status": { "capacity": { "cpu": "4" } } },
+                    { "metadata": { "name": "node-b" }, "status": { "capacity": { "cpu": "2000m" } } }
+                ]
+            }))
+        }),
+    )
+}
+
+fn prometheus_source(
+    address: SocketAddr,
+    query: &str,
+    join_label: &str,
+    join_by: PrometheusJoin,
+) -> Prometheus {
+    Prometheus {
+        client: common::prometheus_client(address),
+        query: query.to_string(),
+        join_label: join_label.to_string(),
+        join_by,
+    }
+}
+
+#[tokio::test]
+async fn prometheus_joins_single_query_by_instance_ip() {
+    let (address, queries) = common::prometheus(|_| {
+        json!([
+            { "metric": { "instance": "10.0.0.1:9100" }, "value": [1700000000, "0.25"] },
+            { "metric": { "instance": "[fd00::2]:9100" }, "value": [1700000000, "0.5"] },
+            { "metric": { "instance": "10.0.0.9:9100" }, "value": [1700000000, "0.75"] }
+        ])
+    })
+    .await;
+    let source = prometheus_source(
+        address,
+        r#"1 - avg by (instance) (irate(node_cpu_seconds_total{mode="idle",instance=~"{{targets}}:9100"}[1m]))"#,
+        "instance",
+        PrometheusJoin::Ip,
+    );
+
+    let usage = source.cpu_usage(&common::workers()).await.unwrap();
+
+    assert_eq!(
+        usage,
+        HashMap::from([("node-a".to_string(), 0.25), ("node-b".to_string(), 0.5)])
+    );
+    assert_eq!(
+        *queries.lock().unwrap(),
+        [
+            r#"1 - avg by (instance) (irate(node_cpu_seconds_total{mode="idle",instance=~"(10\\.0\\.0\\.1|\\[fd00::2\\]):9100"}[1m]))"#
+        ]
+    );
+}
+
+#[tokio::test]
+async fn prometheus_joins_by_hostname_or_node_name() {
+    let (address, _) = common::prometheus(|_| {
+        json!([
+            { "metric": { "instance": "node-a.lan:9100", "node": "node-b" }, "value": [1700000000, "0.25"] }
+        ])
+    })
+    .await;
+
+    let by_hostname = prometheus_source(address, "cpu", "instance", PrometheusJoin::Hostname);
+    let by_nodename = prometheus_source(address, "cpu", "node", PrometheusJoin::NodeName);
+
+    assert_eq!(
+        by_hostname.cpu_usage(&common::workers()).await.unwrap(),
+        HashMap::from([("node-a".to_string(), 0.25)])
+    );
+    assert_eq!(
+        by_nodename.cpu_usage(&common::workers()).await.unwrap(),
+        HashMap::from([("node-b".to_string(), 0.25)])
+    );
+}
+
+#[tokio::test]
+async fn kubelet_summary_is_relative_to_node_capacity() {
+    let app = node_list().route(
+        "/api/v1/nodes/{name}/proxy/stats/summary",
+        get(|Path(name): Path<String>| async move {
+            let usage_nano_cores: u64 = match name.as_str() {
+                "node-a" => 1_000_000_000,
+                _ => 1_500_000_000,
+            };
+            Json(json!({ "node": { "cpu": { "usageNanoCores": usage_nano_cores } } }))
+        }),
+    );
+    let source = KubeletSummary {
+        client: common::kube_client(common::serve(app).await),
+    };
+
+    let usage = source.cpu_usage(&common::workers()).await.unwrap();
+
+    assert_eq!(usage["node-a"], 0.25);
+    assert_eq!(usage["node-b"], 0.75);
+}
+
+#[tokio::test]
+async fn metrics_api_is_relative_to_node_capacity() {
+    let app = node_list().route(
+        "/apis/metrics.k8s.io/v1beta1/nodes",
+        get(|| async {
+            Json(json!({
+                "kind": "NodeMetricsList",
+                "apiVersion": "metrics.k8s.io/v1beta1",
+                "items": [
+                    { "metadata": { "name": "node-a" }, "usage": { "cpu": "2", "memory": "1Gi" } },
+                    { "metadata": { "name": "node-c" }, "usage": { "cpu": "100m", "memory": "1Gi" } }
+                ]
+            }))
+        }),
+    );
+    let source = MetricsApi {
+        client: common::kube_client(common::serve(app).await),
+    };
+
+    let usage = source.cpu_usage(&common::workers()).await.unwrap();
+
+    assert_eq!(usage, HashMap::from([("node-a".to_string(), 0.5)]));
+}
+
+#[tokio::test]
+async fn proc_stat_measures_usage_between_readings() {
+    let path = std::env::temp_dir().join(format!("proberv2-proc-stat-{}", std::process::id()));
+    let source = ProcStat::new(&path, "node-b".to_string());
+
+    std::fs::write(
+        &path,
+        "cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 100 0 100 700 100 0 0 0 0 0\n",
+    )
+    .unwrap();
+    assert!(
+        source
+            .cpu_usage(&common::workers())
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // 200 dari 400 jiffies berikutnya idle atau iowait
+    std::fs::write(&path, "cpu  200 0 200 850 150 0 0 0 0 0\n").unwrap();
+    let usage = source.cpu_usage(&common::workers()).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(usage, HashMap::from([("node-b".to_string(), 0.5)]));
+}
+
+#[test]
+fn cpu_quantity_accepts_kubernetes_suffixes() {
+    assert_eq!(parse_cpu_quantity("4"), Some(4.0));
+    assert_eq!(parse_cpu_quantity("1500m"), Some(1.5));
+    assert_eq!(parse_cpu_quantity("250000000n"), Some(0.25));
+    assert_eq!(parse_cpu_quantity("cpu"), None);
+}
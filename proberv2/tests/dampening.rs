@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use proberv2::{config::DampeningConfig, dampening::dampen};
+
+fn shares(shares: &[(&str, f64)]) -> HashMap<String, f64> {
+    shares
+        .iter()
+        .map(|(node, share)| (node.to_string(), *share))
+        .collect()
+}
+
+fn assert_shares(actual: &HashMap<String, f64>, expected: &[(&str, f64)]) {
+    assert_eq!(actual.len(), expected.len());
+    for (node, share) in expected {
+        assert!(
+            (actual[*node] - share).abs() < 1e-9,
+            "{node}: {} != {share}",
+            actual[*node]
+        );
+    }
+}
+
+const CONFIG: DampeningConfig = DampeningConfig {
+    share_threshold: 0.05,
+    max_share_step: 0.1,
+};
+
+#[test]
+fn first_allocation_applies_target() {
+    let target = shares(&[("a", 0.9), ("b", 0.1)]);
+    assert_shares(&dampen(None, target, &CONFIG), &[("a", 0.9), ("b", 0.1)]);
+}
+
+#[test]
+fn keeps_previous_shares_within_threshold() {
+    let previous = shares(&[("a", 0.5), ("b", 0.5)]);
+    let target = shares(&[("a", 0.54), ("b", 0.46)]);
+    assert_shares(
+        &dampen(Some(&previous), target, &CONFIG),
+        &[("a", 0.5), ("b", 0.5)],
+    );
+}
+
+#[test]
+fn moves_towards_target_in_bounded_steps() {
+    let mut current = shares(&[("a", 0.5), ("b", 0.5)]);
+    let target = shares(&[("a", 0.8), ("b", 0.2)]);
+
+    current = dampen(Some(&current), target.clone(), &CONFIG);
+    assert_shares(&current, &[("a", 0.6), ("b", 0.4)]);
+    current = dampen(Some(&current), target.clone(), &CONFIG);
+    assert_shares(&current, &[("a", 0.7), ("b", 0.3)]);
+    current = dampen(Some(&current), target.clone(), &CONFIG);
+    assert_shares(&current, &[("a", 0.8), ("b", 0.2)]);
+}
+
+#[test]
+fn joining_node_ramps_up_and_departed_node_is_dropped() {
+    let previous = shares(&[("a", 0.5), ("b", 0.5)]);
+    let target = shares(&[("a", 0.5), ("c", 0.5)]);
+    // a tetap 0.5 dan c naik 0.1, kemudian dinormalisasi terhadap total 0.6
+    assert_shares(
+        &dampen(Some(&previous), target, &CONFIG),
+        &[("a", 0.5 / 0.6), ("c", 0.1 / 0.6)],
+    );
+}
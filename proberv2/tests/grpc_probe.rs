@@ -0,0 +1,77 @@
+use std::net::SocketAddr;
+
+use proberv2::grpc_probe::GrpcCheck;
+use tokio::{net::TcpListener, time::Duration};
+use tonic::transport::{Server, server::TcpIncoming};
+use tonic_health::{ServingStatus, server::HealthReporter};
+
+async fn serve() -> (SocketAddr, HealthReporter) {
+    let (reporter, service) = tonic_health::server::health_reporter();
+    reporter
+        .set_service_status("shop.Checkout", ServingStatus::Serving)
+        .await;
+    reporter
+        .set_service_status("shop.Inventory", ServingStatus::NotServing)
+        .await;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpIncoming::from(listener))
+            .await
+            .unwrap()
+    });
+    (address, reporter)
+}
+
+fn check(service: &str) -> GrpcCheck {
+    GrpcCheck {
+        service: service.to_string(),
+        timeout: Duration::from_secs(1),
+        connect_timeout: Duration::from_millis(500),
+    }
+}
+
+#[tokio::test]
+async fn serving_server_passes() {
+    let (address, _reporter) = serve().await;
+
+    assert!(check("").probe(address).await.is_ok());
+    assert!(check("shop.Checkout").probe(address).await.is_ok());
+}
+
+#[tokio::test]
+async fn not_serving_service_fails() {
+    let (address, _reporter) = serve().await;
+
+    let error = check("shop.Inventory").probe(address).await.unwrap_err();
+    assert!(error.to_string().contains("NOT_SERVING"), "{error}");
+}
+
+#[tokio::test]
+async fn status_changes_are_observed() {
+    let (address, reporter) = serve().await;
+
+    reporter
+        .set_service_status("shop.Checkout", ServingStatus::NotServing)
+        .await;
+    assert!(check("shop.Checkout").probe(address).await.is_err());
+
+    reporter
+        .set_service_status("shop.Checkout", ServingStatus::Serving)
+        .await;
+    assert!(check("shop.Checkout").probe(address).await.is_ok());
+}
+
+#[tokio::test]
+async fn unknown_service_and_unreachable_server_fail() {
+    let (address, _reporter) = serve().await;
+    assert!(check("shop.Unknown").probe(address).await.is_err());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed = listener.local_addr().unwrap();
+    drop(listener);
+    assert!(check("").probe(closed).await.is_err());
+}
@@ -0,0 +1,116 @@
+use std::net::SocketAddr;
+
+use axum::{
+    Router,
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+};
+use proberv2::{config::HttpCheckConfig, http_probe::HttpCheck};
+use tokio::net::TcpListener;
+
+async fn serve() -> SocketAddr {
+    let app = Router::new()
+        .route("/", get(|| async { "hello" }))
+        .route("/healthz", get(|| async { r#"{"status":"degraded"}"# }))
+        .route(
+            "/unavailable",
+            get(|| async { (StatusCode::SERVICE_UNAVAILABLE, "down") }),
+        )
+        .route(
+            "/vhost",
+            get(|headers: HeaderMap| async move {
+                match headers.get("host").and_then(|host| host.to_str().ok()) {
+                    Some("app.internal") => StatusCode::OK,
+                    _ => StatusCode::NOT_FOUND,
+                }
+            }),
+        )
+        .route("/echo", post(|body: String| async move { body }));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    address
+}
+
+fn check(config: serde_json::Value) -> HttpCheck {
+    let config: HttpCheckConfig = serde_json::from_value(config).unwrap();
+    HttpCheck::from_config(&config).unwrap()
+}
+
+#[tokio::test]
+async fn default_check_accepts_any_success_status() {
+    let address = serve().await;
+    let client = reqwest::Client::new();
+
+    assert!(
+        check(serde_json::json!({}))
+            .probe(&client, address)
+            .await
+            .is_ok()
+    );
+    let unavailable = check(serde_json::json!({ "path": "/unavailable" }));
+    assert!(unavailable.probe(&client, address).await.is_err());
+}
+
+#[tokio::test]
+async fn accepted_statuses_replace_default_success_range() {
+    let address = serve().await;
+    let client = reqwest::Client::new();
+
+    let check = check(serde_json::json!({
+        "path": "/unavailable",
+        "acceptedStatuses": [503]
+    }));
+    assert!(check.probe(&client, address).await.is_ok());
+}
+
+#[tokio::test]
+async fn sends_configured_method_headers_and_body() {
+    let address = serve().await;
+    let client = reqwest::Client::new();
+
+    let vhost = check(serde_json::json!({
+        "path": "/vhost",
+        "headers": { "Host": "app.internal" }
+    }));
+    assert!(vhost.probe(&client, address).await.is_ok());
+
+    let echo = check(serde_json::json!({
+        "path": "/echo",
+        "method": "POST",
+        "body": "ping",
+        "bodyMatch": { "contains": "ping" }
+    }));
+    assert!(echo.probe(&client, address).await.is_ok());
+}
+
+#[tokio::test]
+async fn degraded_body_counts_as_failure() {
+    let address = serve().await;
+    let client = reqwest::Client::new();
+
+    let contains = check(serde_json::json!({
+        "path": "/healthz",
+        "bodyMatch": { "contains": "\"ok\"" }
+    }));
+    assert!(contains.probe(&client, address).await.is_err());
+
+    let regex = check(serde_json::json!({
+        "path": "/healthz",
+        "bodyMatch": { "regex": r#""status":\s*"(ok|degraded)""# }
+    }));
+    assert!(regex.probe(&client, address).await.is_ok());
+}
+
+#[test]
+fn rejects_invalid_configuration() {
+    for config in [
+        serde_json::json!({ "path": "healthz" }),
+        serde_json::json!({ "method": "NOT A METHOD" }),
+        serde_json::json!({ "headers": { "bad header": "x" } }),
+        serde_json::json!({ "bodyMatch": { "regex": "(" } }),
+    ] {
+        let config: HttpCheckConfig = serde_json::from_value(config).unwrap();
+        assert!(HttpCheck::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use proberv2::icmp_probe::{echo_request, is_echo_reply, ping};
+use tokio::time::Duration;
+
+/// Internet checksum sebuah paket yang checksum-nya benar selalu bernilai 0
+fn fold(packet: &[u8]) -> u16 {
+    let mut sum: u32 = packet
+        .chunks(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], *chunk.get(1).unwrap_or(&0)]) as u32)
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[test]
+fn builds_echo_request_with_valid_checksum() {
+    let packet = echo_request(false, 0x1234, 7);
+
+    assert_eq!(packet[0], 8);
+    assert_eq!(&packet[4..8], &[0x12, 0x34, 0, 7]);
+    assert_eq!(fold(&packet), 0);
+}
+
+#[test]
+fn leaves_icmpv6_checksum_to_kernel() {
+    let packet = echo_request(true, 1, 1);
+
+    assert_eq!(packet[0], 128);
+    assert_eq!(&packet[2..4], &[0, 0]);
+}
+
+#[test]
+fn matches_reply_by_type_identifier_and_sequence() {
+    let mut reply = echo_request(false, 0x1234, 7);
+    reply[0] = 0;
+
+    assert!(is_echo_reply(&reply, false, Some(0x1234), 7));
+    assert!(is_echo_reply(&reply, false, None, 7));
+    assert!(!is_echo_reply(&reply, false, Some(0x4321), 7));
+    assert!(!is_echo_reply(&reply, false, None, 8));
+    assert!(!is_echo_reply(&reply, true, None, 7));
+    assert!(!is_echo_reply(&reply[..6], false, None, 7));
+}
+
+#[tokio::test]
+#[ignore = "requires CAP_NET_RAW or a permissive net.ipv4.ping_group_range"]
+async fn pings_loopback() {
+    for ip in ["127.0.0.1", "::1"] {
+        let rtt = ping(ip.parse().unwrap(), 1, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(rtt < Duration::from_secs(1), "{ip}");
+    }
+}
@@ -0,0 +1,37 @@
+use proberv2::{config::LatencyStatistic, latency_statistic::summarize};
+
+const SAMPLES: [f64; 10] = [12.0, 3.0, 5.0, 9.0, 1.0, 4.0, 250.0, 7.0, 2.0, 6.0];
+
+#[test]
+fn percentiles_use_nearest_rank() {
+    assert_eq!(summarize(&SAMPLES, LatencyStatistic::P50, 0.1), Some(5.0));
+    assert_eq!(summarize(&SAMPLES, LatencyStatistic::P90, 0.1), Some(12.0));
+    assert_eq!(summarize(&SAMPLES, LatencyStatistic::P99, 0.1), Some(250.0));
+}
+
+#[test]
+fn trimmed_mean_drops_both_tails() {
+    // 1.0 dan 250.0 dibuang
+    let expected = (2.0 + 3.0 + 4.0 + 5.0 + 6.0 + 7.0 + 9.0 + 12.0) / 8.0;
+    assert_eq!(
+        summarize(&SAMPLES, LatencyStatistic::TrimmedMean, 0.1),
+        Some(expected)
+    );
+    assert_eq!(
+        summarize(&[4.0, 8.0], LatencyStatistic::TrimmedMean, 0.5),
+        Some(4.0)
+    );
+}
+
+#[test]
+fn single_sample_is_returned_as_is() {
+    for statistic in [
+        LatencyStatistic::P50,
+        LatencyStatistic::P90,
+        LatencyStatistic::P99,
+        LatencyStatistic::TrimmedMean,
+    ] {
+        assert_eq!(summarize(&[42.0], statistic, 0.1), Some(42.0));
+    }
+    assert_eq!(summarize(&[], LatencyStatistic::P50, 0.1), None);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use std::collections::HashMap;
+
+use proberv2::{
+    config::PrometheusJoin,
+    cpu_source::Prometheus,
+    memory_source::{MemorySource, ProcPressure, parse_meminfo, parse_pressure},
+};
+use serde_json::json;
+
+#[test]
+fn meminfo_reports_unavailable_fraction() {
+    let meminfo =
+        "MemTotal:        8000000 kB\nMemFree:          500000 kB\nMemAvailable:    2000000 kB\n";
+
+    assert_eq!(parse_meminfo(meminfo).unwrap(), 0.75);
+    assert!(parse_meminfo("MemTotal: 8000000 kB\n").is_err());
+}
+
+#[test]
+fn pressure_reads_some_avg10() {
+    let pressure = "some avg10=12.50 avg60=3.00 avg300=1.00 total=123456\nfull avg10=5.00 avg60=1.00 avg300=0.00 total=456\n";
+
+    assert_eq!(parse_pressure(pressure).unwrap(), 0.125);
+    assert!(parse_pressure("").is_err());
+}
+
+#[tokio::test]
+async fn proc_pressure_only_measures_local_node() {
+    let path = std::env::temp_dir().join(format!("proberv2-pressure-{}", std::process::id()));
+    std::fs::write(&path, "some avg10=50.00 avg60=0.00 avg300=0.00 total=0\n").unwrap();
+
+    let local = ProcPressure {
+        path: path.clone(),
+        node_name: "node-a".to_string(),
+    };
+    let remote = ProcPressure {
+        path: path.clone(),
+        node_name: "node-z".to_string(),
+    };
+    let local = local.memory_pressure(&common::workers()).await.unwrap();
+    let remote = remote.memory_pressure(&common::workers()).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(local, HashMap::from([("node-a".to_string(), 0.5)]));
+    assert!(remote.is_empty());
+}
+
+#[tokio::test]
+async fn prometheus_memory_query_joins_by_instance() {
+    let (address, _) = common::prometheus(|_| {
+        json!([
+            { "metric": { "instance": "10.0.0.1:9100" }, "value": [1700000000, "0.9"] }
+        ])
+    })
+    .await;
+    let source = Prometheus {
+        client: common::prometheus_client(address),
+        query: "1 - node_memory_MemAvailable_bytes / node_memory_MemTotal_bytes".to_string(),
+        join_label: "instance".to_string(),
+        join_by: PrometheusJoin::Ip,
+    };
+
+    let pressure = source.memory_pressure(&common::workers()).await.unwrap();
+
+    assert_eq!(pressure, HashMap::from([("node-a".to_string(), 0.9)]));
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use proberv2::{
+    config::PrometheusJoin,
+    cpu_source::Prometheus,
+    network_source::{
+        InterfaceCounters, NetworkSource, PrometheusNetwork, combine, read_counters, saturation,
+    },
+};
+use serde_json::json;
+
+fn write_interface(root: &Path, name: &str, physical: bool, speed: &str, rx_bytes: u64) {
+    let interface = root.join(name);
+    std::fs::create_dir_all(interface.join("statistics")).unwrap();
+    if physical {
+        std::fs::create_dir_all(interface.join("device")).unwrap();
+    }
+    std::fs::write(interface.join("speed"), speed).unwrap();
+    for (statistic, value) in [
+        ("rx_bytes", rx_bytes),
+        ("tx_bytes", 10),
+        ("rx_packets", 90),
+        ("tx_packets", 10),
+        ("rx_dropped", 1),
+        ("tx_dropped", 0),
+        ("rx_errors", 1),
+        ("tx_errors", 0),
+    ] {
+        std::fs::write(
+            interface.join("statistics").join(statistic),
+            format!("{value}\n"),
+        )
+        .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn counters_skip_virtual_interfaces() {
+    let root = std::env::temp_dir().join(format!("proberv2-net-{}", std::process::id()));
+    write_interface(&root, "eth0", true, "1000\n", 500);
+    write_interface(&root, "wlan0", true, "-1\n", 0);
+    write_interface(&root, "veth1234", false, "10000\n", 0);
+
+    let counters = read_counters(&root).await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(counters.len(), 2);
+    assert_eq!(
+        counters["eth0"],
+        InterfaceCounters {
+            speed_mbps: Some(1000.0),
+            rx_bytes: 500,
+            tx_bytes: 10,
+            packets: 100,
+            faults: 2,
+        }
+    );
+    assert_eq!(counters["wlan0"].speed_mbps, None);
+}
+
+#[test]
+fn saturation_combines_busiest_link_and_fault_ratio() {
+    let counters = |rx_bytes, packets, faults| {
+        HashMap::from([(
+            "eth0".to_string(),
+            InterfaceCounters {
+                speed_mbps: Some(100.0),
+                rx_bytes,
+                tx_bytes: 0,
+                packets,
+                faults,
+            },
+        )])
+    };
+
+    // 2,5 MB dalam 1 detik pada link 100 Mbit/s berarti utilisasi 0,2
+    let saturated = saturation(
+        &counters(0, 0, 0),
+        &counters(2_500_000, 1000, 100),
+        Duration::from_secs(1),
+    );
+
+    assert!((saturated - 0.3).abs() < 1e-9);
+    assert_eq!(combine(0.9, 0.5), 1.0);
+}
+
+#[tokio::test]
+async fn prometheus_network_adds_error_ratio_to_utilization() {
+    let (address, _) = common::prometheus(|query| {
+        let value = if query == "utilization" { "0.5" } else { "0.1" };
+        json!([
+            { "metric": { "instance": "10.0.0.1:9100" }, "value": [1700000000, value] }
+        ])
+    })
+    .await;
+    let prometheus = |query: &str| Prometheus {
+        client: common::prometheus_client(address),
+        query: query.to_string(),
+        join_label: "instance".to_string(),
+        join_by: PrometheusJoin::Ip,
+    };
+    let source = PrometheusNetwork {
+        utilization: prometheus("utilization"),
+        errors: prometheus("errors"),
+    };
+    let saturation = source.network_saturation(&common::workers()).await.unwrap();
+
+    assert!((saturation["node-a"] - 0.6).abs() < 1e-9);
+}
@@ -0,0 +1,424 @@
+mod common;
+
+use std::collections::HashMap;
+
+use proberv2::{
+    actor::{EndpointDatapoint, Protocol, ServicePort},
+    config::{EligibilityConfig, FallbackPolicy},
+    nftables_backend::FakeNftables,
+    setup_nftables::install_base_ruleset,
+    teardown_nftables::teardown_nftables,
+    update_nftables::{RulesetUpdate, ServiceRuleset, update_nftables},
+};
+use serde_json::Value;
+
+#[test]
+fn base_ruleset_covers_both_address_families() {
+    let backend = FakeNftables::new();
+    let ips = ["10.0.0.1".parse().unwrap(), "fd00::1".parse().unwrap()];
+
+    install_base_ruleset(&common::config(), &backend, &ips).unwrap();
+
+    common::assert_golden("base_ruleset", &backend.applied());
+}
+
+#[tokio::test]
+async fn update_splits_slots_by_node_score() {
+    let backend = FakeNftables::new();
+    let service = common::service(&[
+        ("node-a", &["10.0.0.1"]),
+        ("node-b", &["10.0.0.2", "10.0.0.3"]),
+    ]);
+    let datapoints = common::datapoints(&[("node-a", 10.0, 0.5), ("node-b", 20.0, 0.5)]);
+
+    let ruleset = update_nftables(
+        common::config(),
+        &backend,
+        service,
+        datapoints,
+        &HashMap::new(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert!(ruleset.applied().is_some());
+    common::assert_golden("update_weighted", &backend.applied());
+}
+
+#[tokio::test]
+async fn update_splits_node_share_by_pod_latency() {
+    let backend = FakeNftables::new();
+    let service = common::service(&[
+        ("node-a", &["10.0.0.1"]),
+        ("node-b", &["10.0.0.2", "10.0.0.3"]),
+    ]);
+    let datapoints = common::datapoints(&[("node-a", 10.0, 0.5), ("node-b", 20.0, 0.5)]);
+    let datapoint_by_endpoint = HashMap::from([
+        (
+            "10.0.0.2".parse().unwrap(),
+            EndpointDatapoint {
+                latency: Some(10.0),
+                ..Default::default()
+            },
+        ),
+        (
+            "10.0.0.3".parse().unwrap(),
+            EndpointDatapoint {
+                latency: Some(30.0),
+                ..Default::default()
+            },
+        ),
+    ]);
+
+    let ruleset = update_nftables(
+        common::config(),
+        &backend,
+        service,
+        datapoints,
+        &datapoint_by_endpoint,
+        None,
+    )
+    .await
+    .unwrap();
+
+    // porsi node-b (1/3) dibagi 3:1 kepada pod yang lebih cepat
+    let slots = slots_by_ip(&ruleset.applied().unwrap().ruleset);
+    assert_eq!(slots["10.0.0.1"], 67);
+    assert_eq!(slots["10.0.0.2"], 25);
+    assert_eq!(slots["10.0.0.3"], 8);
+}
+
+#[tokio::test]
+async fn small_pod_latency_changes_keep_endpoint_split() {
+    let backend = FakeNftables::new();
+    let mut config = common::config();
+    config.nftables.probability_cap = 100000;
+    config.dampening.share_threshold = 0.05;
+    let service = common::service(&[
+        ("node-a", &["10.0.0.1"]),
+        ("node-b", &["10.0.0.2", "10.0.0.3"]),
+    ]);
+    let datapoints = common::datapoints(&[("node-a", 10.0, 0.5), ("node-b", 20.0, 0.5)]);
+    let pod_latencies = |fast: f64, slow: f64| {
+        HashMap::from([
+            (
+                "10.0.0.2".parse().unwrap(),
+                EndpointDatapoint {
+                    latency: Some(fast),
+                    ..Default::default()
+                },
+            ),
+            (
+                "10.0.0.3".parse().unwrap(),
+                EndpointDatapoint {
+                    latency: Some(slow),
+                    ..Default::default()
+                },
+            ),
+        ])
+    };
+
+    let previous = update_nftables(
+        config.clone(),
+        &backend,
+        service.clone(),
+        datapoints.clone(),
+        &pod_latencies(10.0, 30.0),
+        None,
+    )
+    .await
+    .unwrap();
+    backend.take();
+    let ruleset = update_nftables(
+        config,
+        &backend,
+        service,
+        datapoints,
+        &pod_latencies(10.5, 29.0),
+        previous.applied(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(ruleset, previous);
+    assert!(backend.applied().is_empty());
+}
+
+#[tokio::test]
+async fn update_skips_transaction_for_unchanged_state() {
+    let backend = FakeNftables::new();
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    let datapoints = common::datapoints(&[("node-a", 10.0, 0.5), ("node-b", 20.0, 0.5)]);
+
+    let previous = update_nftables(
+        common::config(),
+        &backend,
+        service.clone(),
+        datapoints.clone(),
+        &HashMap::new(),
+        None,
+    )
+    .await
+    .unwrap();
+    let ruleset = update_nftables(
+        common::config(),
+        &backend,
+        service.clone(),
+        datapoints,
+        &HashMap::new(),
+        previous.applied(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(ruleset, previous);
+    assert_eq!(backend.take().len(), 1);
+
+    let datapoints = common::datapoints(&[("node-a", 10.0, 0.5), ("node-b", 10.0, 0.5)]);
+    update_nftables(
+        common::config(),
+        &backend,
+        service,
+        datapoints,
+        &HashMap::new(),
+        previous.applied(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(backend.take().len(), 1);
+}
+
+#[tokio::test]
+async fn update_excludes_node_under_memory_pressure() {
+    let backend = FakeNftables::new();
+    let service = common::service(&[
+        ("node-a", &["10.0.0.1"]),
+        ("node-b", &["10.0.0.2"]),
+        ("node-c", &["10.0.0.3"]),
+    ]);
+    let mut datapoints = common::datapoints(&[
+        ("node-a", 10.0, 0.5),
+        ("node-b", 10.0, 0.5),
+        ("node-c", 10.0, 0.5),
+    ]);
+    if let Some(Some(datapoint)) = datapoints.get_mut("node-c") {
+        datapoint.memory = 0.97;
+    }
+
+    let ruleset = update_nftables(
+        common::config(),
+        &backend,
+        service,
+        datapoints,
+        &HashMap::new(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let slots = slots_by_ip(&ruleset.applied().unwrap().ruleset);
+    assert_eq!(slots["10.0.0.1"], 50);
+    assert_eq!(slots["10.0.0.2"], 50);
+    assert!(!slots.contains_key("10.0.0.3"));
+}
+
+#[tokio::test]
+async fn update_skips_service_without_scored_nodes() {
+    let backend = FakeNftables::new();
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    let datapoints = common::datapoints(&[("node-a", 10.0, 0.5)]);
+
+    let ruleset = update_nftables(
+        common::config(),
+        &backend,
+        service,
+        datapoints,
+        &HashMap::new(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(ruleset, RulesetUpdate::Skipped);
+    assert!(backend.applied().is_empty());
+}
+
+#[tokio::test]
+async fn update_admits_idle_node_and_applies_service_eligibility() {
+    let backend = FakeNftables::new();
+    let service = common::service(&[
+        ("node-a", &["10.0.0.1"]),
+        ("node-b", &["10.0.0.2"]),
+        ("node-c", &["10.0.0.3"]),
+    ]);
+    let datapoints = common::datapoints(&[
+        ("node-a", 10.0, 0.0),
+        ("node-b", 10.0, 0.0),
+        ("node-c", 80.0, 0.0),
+    ]);
+    let mut config = common::config();
+    config.service_eligibility.insert(
+        "default/web".to_string(),
+        EligibilityConfig {
+            max_latency_ms: Some(50.0),
+            ..Default::default()
+        },
+    );
+
+    let ruleset = update_nftables(config, &backend, service, datapoints, &HashMap::new(), None)
+        .await
+        .unwrap();
+
+    let slots = slots_by_ip(&ruleset.applied().unwrap().ruleset);
+    assert_eq!(slots["10.0.0.1"], 50);
+    assert_eq!(slots["10.0.0.2"], 50);
+    assert!(!slots.contains_key("10.0.0.3"));
+}
+
+#[tokio::test]
+async fn update_falls_back_when_fewer_than_two_nodes_are_eligible() {
+    let service = common::service(&[
+        ("node-a", &["10.0.0.1"]),
+        ("node-b", &["10.0.0.2", "10.0.0.3"]),
+    ]);
+    // kedua node melewati batas CPU, node-b dengan skor yang jauh lebih rendah
+    let datapoints = common::datapoints(&[("node-a", 10.0, 0.96), ("node-b", 40.0, 0.99)]);
+    let update = |fallback| {
+        let backend = FakeNftables::new();
+        let mut config = common::config();
+        config.eligibility.fallback = fallback;
+        let (service, datapoints) = (service.clone(), datapoints.clone());
+        async move {
+            let update =
+                update_nftables(config, &backend, service, datapoints, &HashMap::new(), None)
+                    .await
+                    .unwrap();
+            (update, backend.take())
+        }
+    };
+
+    let (ruleset, applied) = update(FallbackPolicy::EqualWeights).await;
+    let slots = slots_by_ip(&ruleset.applied().unwrap().ruleset);
+    assert_eq!(slots["10.0.0.1"], 50);
+    assert_eq!(slots["10.0.0.2"], 25);
+    assert_eq!(slots["10.0.0.3"], 25);
+    assert_eq!(applied.len(), 1);
+
+    // keepLastKnown merupakan perlakuan default
+    let (ruleset, applied) = update(FallbackPolicy::default()).await;
+    assert_eq!(ruleset, RulesetUpdate::Skipped);
+    assert!(applied.is_empty());
+
+    let (ruleset, applied) = update(FallbackPolicy::KubeProxy).await;
+    assert_eq!(ruleset, RulesetUpdate::Released);
+    assert!(applied.is_empty());
+}
+
+#[tokio::test]
+async fn keep_last_known_drops_nodes_without_datapoint() {
+    let backend = FakeNftables::new();
+    let service = common::service(&[
+        ("node-a", &["10.0.0.1"]),
+        ("node-b", &["10.0.0.2"]),
+        ("node-c", &["10.0.0.3"]),
+    ]);
+    // node-a dan node-b melewati batas CPU, node-c di-eject sehingga tidak memiliki datapoint
+    let mut datapoints = common::datapoints(&[("node-a", 10.0, 0.96), ("node-b", 20.0, 0.97)]);
+    datapoints.insert("node-c".to_string(), None);
+    let previous = ServiceRuleset {
+        ruleset: Value::Null,
+        share_by_nodename: HashMap::from([
+            ("node-a".to_string(), 0.5),
+            ("node-b".to_string(), 0.3),
+            ("node-c".to_string(), 0.2),
+        ]),
+        split_by_endpoint: HashMap::new(),
+    };
+
+    let ruleset = update_nftables(
+        common::config(),
+        &backend,
+        service,
+        datapoints,
+        &HashMap::new(),
+        Some(&previous),
+    )
+    .await
+    .unwrap();
+
+    let applied = ruleset.applied().unwrap();
+    assert_eq!(applied.share_by_nodename.len(), 2);
+    assert!((applied.share_by_nodename["node-a"] - 0.625).abs() < 1e-9);
+    assert!((applied.share_by_nodename["node-b"] - 0.375).abs() < 1e-9);
+    let slots = slots_by_ip(&applied.ruleset);
+    assert_eq!(slots["10.0.0.1"], 63);
+    assert_eq!(slots["10.0.0.2"], 37);
+    assert!(!slots.contains_key("10.0.0.3"));
+}
+
+#[tokio::test]
+async fn update_falls_back_when_every_pod_is_cpu_saturated() {
+    let service = common::service(&[("node-a", &["10.0.0.1"]), ("node-b", &["10.0.0.2"])]);
+    let mut datapoints = common::datapoints(&[("node-a", 10.0, 0.5), ("node-b", 20.0, 0.5)]);
+    for datapoint in datapoints.values_mut().flatten() {
+        datapoint.pod_cpu = 1.0;
+    }
+    let update = |fallback| {
+        let backend = FakeNftables::new();
+        let mut config = common::config();
+        config.eligibility.fallback = fallback;
+        let (service, datapoints) = (service.clone(), datapoints.clone());
+        async move {
+            update_nftables(config, &backend, service, datapoints, &HashMap::new(), None)
+                .await
+                .unwrap()
+        }
+    };
+
+    // seluruh skor power law bernilai 0 sehingga porsi tidak dapat dibagi berdasarkan skor
+    let ruleset = update(FallbackPolicy::EqualWeights).await;
+    let slots = slots_by_ip(&ruleset.applied().unwrap().ruleset);
+    assert_eq!(slots["10.0.0.1"], 50);
+    assert_eq!(slots["10.0.0.2"], 50);
+
+    assert_eq!(
+        update(FallbackPolicy::KubeProxy).await,
+        RulesetUpdate::Released
+    );
+}
+
+#[tokio::test]
+async fn teardown_removes_map_element_before_chain() {
+    let backend = FakeNftables::new();
+    let port = ServicePort {
+        protocol: Protocol::Udp,
+        nodeport: 30053,
+        targetport: 53,
+    };
+
+    teardown_nftables(common::config(), &backend, "default/dns".to_string(), port)
+        .await
+        .unwrap();
+
+    common::assert_golden("teardown", &backend.applied());
+}
+
+/// Jumlah slot setiap IP endpoint pada aturan DNAT sebuah ruleset
+fn slots_by_ip(ruleset: &Value) -> HashMap<String, u64> {
+    let mut slots = HashMap::new();
+    for command in ruleset["nftables"].as_array().unwrap() {
+        let Some(expr) = command["add"]["rule"]["expr"].as_array() else {
+            continue;
+        };
+        let set = expr[2]["dnat"]["addr"]["map"]["data"]["set"]
+            .as_array()
+            .unwrap();
+        for item in set {
+            let range = &item[0]["range"];
+            let ip = item[1].as_str().unwrap().to_string();
+            *slots.entry(ip).or_default() +=
+                range[1].as_u64().unwrap() - range[0].as_u64().unwrap() + 1;
+        }
+    }
+    slots
+}
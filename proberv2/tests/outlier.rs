@@ -0,0 +1,117 @@
+use proberv2::{
+    config::OutlierConfig,
+    outlier::{OutlierDetector, Transition},
+};
+use tokio::time::{Duration, Instant};
+
+fn detector() -> OutlierDetector {
+    OutlierDetector::new(OutlierConfig {
+        consecutive_failures: 3,
+        failure_rate_threshold: 0.5,
+        failure_rate_window: 4,
+        base_ejection_time: 10,
+        max_ejection_time: 25,
+        readmit_successes: 2,
+    })
+}
+
+fn target() -> (String, String) {
+    ("default/web".to_string(), "node-a".to_string())
+}
+
+fn record_all(
+    detector: &mut OutlierDetector,
+    outcomes: &[bool],
+    now: Instant,
+) -> Vec<Option<Transition>> {
+    outcomes
+        .iter()
+        .map(|success| detector.record(&target(), *success, now))
+        .collect()
+}
+
+#[test]
+fn ejects_after_consecutive_failures() {
+    let mut detector = detector();
+    let now = Instant::now();
+
+    let transitions = record_all(&mut detector, &[false, false, false], now);
+
+    assert_eq!(
+        transitions,
+        [
+            None,
+            None,
+            Some(Transition::Ejected(Duration::from_secs(10)))
+        ]
+    );
+    assert!(detector.is_ejected(&target()));
+}
+
+#[test]
+fn ejects_when_failure_rate_exceeds_threshold() {
+    let mut detector = detector();
+    let now = Instant::now();
+
+    let transitions = record_all(&mut detector, &[true, false, true, false], now);
+
+    assert_eq!(
+        transitions.last().unwrap(),
+        &Some(Transition::Ejected(Duration::from_secs(10)))
+    );
+}
+
+#[test]
+fn readmits_only_after_ejection_time_and_successful_probes() {
+    let mut detector = detector();
+    let now = Instant::now();
+    record_all(&mut detector, &[false, false, false], now);
+
+    // probe yang berhasil selama masa ejection tidak diperhitungkan
+    assert_eq!(record_all(&mut detector, &[true, true], now), [None, None]);
+
+    let later = now + Duration::from_secs(10);
+    assert_eq!(
+        record_all(&mut detector, &[true, false, true, true], later),
+        [None, None, None, Some(Transition::Readmitted)]
+    );
+    assert!(!detector.is_ejected(&target()));
+}
+
+#[test]
+fn backs_off_exponentially_up_to_maximum() {
+    let mut detector = detector();
+    let mut now = Instant::now();
+    let mut durations = Vec::new();
+
+    for _ in 0..3 {
+        let transitions = record_all(&mut detector, &[false, false, false], now);
+        let Some(Some(Transition::Ejected(duration))) = transitions.last() else {
+            panic!("expected ejection, got {transitions:?}");
+        };
+        durations.push(duration.as_secs());
+        now += *duration;
+        record_all(&mut detector, &[true, true], now);
+    }
+
+    assert_eq!(durations, [10, 20, 25]);
+}
+
+#[test]
+fn empty_failure_rate_window_still_ejects_on_failure_rate() {
+    let mut detector = OutlierDetector::new(OutlierConfig {
+        consecutive_failures: 3,
+        failure_rate_threshold: 0.5,
+        failure_rate_window: 0,
+        base_ejection_time: 10,
+        max_ejection_time: 25,
+        readmit_successes: 2,
+    });
+
+    let transitions = record_all(&mut detector, &[true, false], Instant::now());
+
+    assert_eq!(
+        transitions,
+        [None, Some(Transition::Ejected(Duration::from_secs(10)))]
+    );
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use std::collections::HashMap;
+
+use axum::{Json, Router, extract::Path, routing::get};
+use proberv2::pod_cpu_source::{KubeletPodSummary, PodCpuSource, PodRef, PrometheusPodCpu};
+use serde_json::json;
+
+fn pod(name: &str, node: &str) -> PodRef {
+    PodRef {
+        namespace: "default".to_string(),
+        name: name.to_string(),
+        node: node.to_string(),
+    }
+}
+
+fn pods() -> Vec<PodRef> {
+    vec![
+        pod("web-a", "node-a"),
+        pod("web-b", "node-b"),
+        pod("web-c", "node-b"),
+    ]
+}
+
+#[tokio::test]
+async fn prometheus_takes_worse_of_usage_and_throttling() {
+    let (address, queries) = common::prometheus(|query| {
+        if query.starts_with("usage") {
+            json!([
+                { "metric": { "namespace": "default", "pod": "web-a" }, "value": [1700000000, "0.4"] },
+                { "metric": { "namespace": "default", "pod": "web-b" }, "value": [1700000000, "1.3"] },
+                { "metric": { "namespace": "other", "pod": "web-c" }, "value": [1700000000, "0.9"] }
+            ])
+        } else {
+            json!([
+                { "metric": { "namespace": "default", "pod": "web-a" }, "value": [1700000000, "0.6"] }
+            ])
+        }
+    })
+    .await;
+    let source = PrometheusPodCpu {
+        client: common::prometheus_client(address),
+        usage_query: r#"usage{pod=~"{{pods}}"}"#.to_string(),
+        throttling_query: "throttling".to_string(),
+    };
+
+    let saturation = source.pod_cpu_saturation(&pods()).await.unwrap();
+
+    // web-c pada namespace lain bukan endpoint Service
+    assert_eq!(
+        saturation,
+        HashMap::from([(pod("web-a", "node-a"), 0.6), (pod("web-b", "node-b"), 1.0)])
+    );
+    assert_eq!(
+        queries.lock().unwrap()[0],
+        r#"usage{pod=~"(web\\-a|web\\-b|web\\-c)"}"#
+    );
+}
+
+#[tokio::test]
+async fn kubelet_summary_is_relative_to_pod_limit() {
+    let app = Router::new()
+        .route(
+            "/api/v1/namespaces/default/pods",
+            get(|| async {
+                let container = |name: &str, limits: serde_json::Value| {
+                    json!({ "name": name, "image": "web", "resources": { "limits": limits } })
+                };
+                Json(json!({
+                    "apiVersion": "v1",
+                    "kind": "PodList",
+                    "metadata": {},
+                    "items": [
+                        {
+                            "metadata": { "name": "web-a", "namespace": "default" },
+                            "spec": { "containers": [
+                                container("app", json!({ "cpu": "500m" })),
+                                container("sidecar", json!({ "cpu": "500m" }))
+                            ] }
+                        },
+                        {
+                            "metadata": { "name": "web-b", "namespace": "default" },
+                            "spec": { "containers": [container("app", json!({ "cpu": "2" }))] }
+                        },
+                        {
+                            "metadata": { "name": "web-c", "namespace": "default" },
+                            "spec": { "containers": [container("app", json!({ "memory": "1Gi" }))] }
+                        }
+                    ]
+                }))
+            }),
+        )
+        .route(
+            "/api/v1/nodes/{name}/proxy/stats/summary",
+            get(|Path(name): Path<String>| async move {
+                let pods = match name.as_str() {
+                    "node-a" => json!([
+                        { "podRef": { "name": "web-a", "namespace": "default" }, "cpu": { "usageNanoCores": 250_000_000u64 } }
+                    ]),
+                    _ => json!([
+                        { "podRef": { "name": "web-b", "namespace": "default" }, "cpu": { "usageNanoCores": 1_500_000_000u64 } },
+                        { "podRef": { "name": "web-c", "namespace": "default" }, "cpu": { "usageNanoCores": 100_000_000u64 } }
+                    ]),
+                };
+                Json(json!({ "node": { "cpu": { "usageNanoCores": 0 } }, "pods": pods }))
+            }),
+        );
+    let source = KubeletPodSummary {
+        client: common::kube_client(common::serve(app).await),
+    };
+
+    let saturation = source.pod_cpu_saturation(&pods()).await.unwrap();
+
+    // web-c tidak memiliki limit CPU
+    assert_eq!(
+        saturation,
+        HashMap::from([
+            (pod("web-a", "node-a"), 0.25),
+            (pod("web-b", "node-b"), 0.75)
+        ])
+    );
+}
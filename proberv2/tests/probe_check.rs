@@ -0,0 +1,47 @@
+use proberv2::{
+    config::{HttpProbeConfig, ProbeCheckConfig},
+    probe_check::ProbeCheck,
+};
+use tokio::net::TcpListener;
+
+fn check(config: serde_json::Value) -> ProbeCheck {
+    let config: ProbeCheckConfig = serde_json::from_value(config).unwrap();
+    ProbeCheck::from_config(&config, &HttpProbeConfig::default()).unwrap()
+}
+
+#[test]
+fn defaults_to_http_mode() {
+    let check = check(serde_json::json!({ "path": "/healthz" }));
+    assert!(matches!(check, ProbeCheck::Http(_)));
+}
+
+#[tokio::test]
+async fn tcp_mode_measures_handshake_only() {
+    // listener tidak pernah menerima koneksi maupun membalas data,
+    // handshake tetap diselesaikan oleh kernel
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let check = check(serde_json::json!({ "mode": "tcp" }));
+
+    assert!(check.probe(&reqwest::Client::new(), address).await.is_ok());
+}
+
+#[tokio::test]
+async fn tcp_mode_fails_on_closed_port() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    let check = check(serde_json::json!({ "mode": "tcp" }));
+
+    assert!(check.probe(&reqwest::Client::new(), address).await.is_err());
+}
+
+#[test]
+fn grpc_mode_carries_service_name_and_timeouts() {
+    let check = check(serde_json::json!({ "mode": "grpc", "grpcService": "shop.Checkout" }));
+    let ProbeCheck::Grpc(check) = check else {
+        panic!("expected grpc check, got {check:?}");
+    };
+    assert_eq!(check.service, "shop.Checkout");
+    assert_eq!(check.timeout.as_millis(), 1000);
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use proberv2::{
+    actor::ScorePair,
+    config::ScoringConfig,
+    scoring::{InverseLatency, PowerLaw, ScoringPolicy, Softmax, WeightedLinear, scoring_policy},
+};
+
+fn datapoints() -> Vec<ScorePair> {
+    vec![
+        ScorePair {
+            latency: 10.0,
+            cpu: 0.5,
+            ..Default::default()
+        },
+        ScorePair {
+            latency: 20.0,
+            cpu: 0.0,
+            ..Default::default()
+        },
+    ]
+}
+
+fn scores(policy: &dyn ScoringPolicy) -> Vec<f64> {
+    let datapoints = datapoints();
+    policy.scores(&datapoints.iter().collect::<Vec<_>>())
+}
+
+fn assert_close(actual: &[f64], expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{actual:?} != {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn power_law_matches_original_formula() {
+    assert_close(&scores(&PowerLaw { exponent: 1.0 }), &[0.05, 0.05]);
+    assert_close(
+        &scores(&PowerLaw { exponent: 0.5 }),
+        &[0.05f64.sqrt(), 0.05f64.sqrt()],
+    );
+}
+
+#[test]
+fn weighted_linear_normalizes_latency_against_fastest_node() {
+    let policy = WeightedLinear {
+        weight_latency: 0.7,
+        weight_cpu: 0.3,
+        weight_rtt: 0.0,
+        weight_memory: 0.0,
+        weight_network: 0.0,
+        weight_pod_cpu: 0.0,
+    };
+    assert_close(&scores(&policy), &[0.7 + 0.15, 0.35 + 0.3]);
+}
+
+#[test]
+fn memory_pressure_lowers_node_score() {
+    let datapoints = [
+        ScorePair {
+            latency: 10.0,
+            cpu: 0.5,
+            ..Default::default()
+        },
+        ScorePair {
+            latency: 10.0,
+            cpu: 0.5,
+            memory: 0.8,
+            ..Default::default()
+        },
+    ];
+    let datapoints: Vec<&ScorePair> = datapoints.iter().collect();
+
+    assert_close(
+        &PowerLaw { exponent: 1.0 }.scores(&datapoints),
+        &[0.05, 0.01],
+    );
+    let policy = WeightedLinear {
+        weight_latency: 0.0,
+        weight_cpu: 0.0,
+        weight_rtt: 0.0,
+        weight_memory: 1.0,
+        weight_network: 0.0,
+        weight_pod_cpu: 0.0,
+    };
+    assert_close(&policy.scores(&datapoints), &[1.0, 0.2]);
+}
+
+#[test]
+fn inverse_latency_ignores_cpu() {
+    assert_close(&scores(&InverseLatency), &[0.1, 0.05]);
+}
+
+#[test]
+fn softmax_temperature_controls_concentration() {
+    let sharp = scores(&Softmax { temperature: 0.01 });
+    let flat = scores(&Softmax { temperature: 100.0 });
+
+    // utilitas kedua node sama (0.5), sehingga porsi tetap seimbang
+    assert_close(&sharp, &[1.0, 1.0]);
+    assert_close(&flat, &[1.0, 1.0]);
+
+    let datapoints = [
+        ScorePair {
+            latency: 10.0,
+            cpu: 0.2,
+            ..Default::default()
+        },
+        ScorePair {
+            latency: 20.0,
+            cpu: 0.2,
+            ..Default::default()
+        },
+    ];
+    let datapoints: Vec<&ScorePair> = datapoints.iter().collect();
+    let sharp = Softmax { temperature: 0.01 }.scores(&datapoints);
+    let flat = Softmax { temperature: 100.0 }.scores(&datapoints);
+    assert!(sharp[1] / sharp[0] < 1e-6);
+    assert!(flat[1] / flat[0] > 0.99);
+}
+
+#[test]
+fn network_saturation_lowers_node_score() {
+    let datapoints = [
+        ScorePair {
+            latency: 10.0,
+            cpu: 0.5,
+            ..Default::default()
+        },
+        ScorePair {
+            latency: 10.0,
+            cpu: 0.5,
+            network: 0.5,
+            ..Default::default()
+        },
+    ];
+    let datapoints: Vec<&ScorePair> = datapoints.iter().collect();
+
+    assert_close(
+        &PowerLaw { exponent: 1.0 }.scores(&datapoints),
+        &[0.05, 0.025],
+    );
+    let policy = WeightedLinear {
+        weight_latency: 0.0,
+        weight_cpu: 0.0,
+        weight_rtt: 0.0,
+        weight_memory: 0.0,
+        weight_network: 1.0,
+        weight_pod_cpu: 0.0,
+    };
+    assert_close(&policy.scores(&datapoints), &[1.0, 0.5]);
+}
+
+#[test]
+fn pod_cpu_saturation_lowers_node_score() {
+    let datapoints = [
+        ScorePair {
+            latency: 10.0,
+            cpu: 0.5,
+            ..Default::default()
+        },
+        ScorePair {
+            latency: 10.0,
+            cpu: 0.5,
+            pod_cpu: 0.9,
+            ..Default::default()
+        },
+    ];
+    let datapoints: Vec<&ScorePair> = datapoints.iter().collect();
+
+    assert_close(
+        &PowerLaw { exponent: 1.0 }.scores(&datapoints),
+        &[0.05, 0.005],
+    );
+    let policy = WeightedLinear {
+        weight_latency: 0.0,
+        weight_cpu: 0.0,
+        weight_rtt: 0.0,
+        weight_memory: 0.0,
+        weight_network: 0.0,
+        weight_pod_cpu: 1.0,
+    };
+    assert_close(&policy.scores(&datapoints), &[1.0, 0.1]);
+}
+
+#[test]
+fn scoring_policy_rejects_parameters_producing_invalid_shares() {
+    let mut config = common::config();
+    assert!(scoring_policy(&config).is_ok());
+
+    for temperature in [0.0, -1.0, f64::NAN] {
+        config.scoring = ScoringConfig::Softmax { temperature };
+        assert!(scoring_policy(&config).is_err());
+    }
+
+    config.scoring = ScoringConfig::PowerLaw;
+    config.alpha.score_exponent = f64::INFINITY;
+    assert!(scoring_policy(&config).is_err());
+}
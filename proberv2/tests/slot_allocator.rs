@@ -0,0 +1,101 @@
+use proberv2::slot_allocator::{allocate, slots};
+use proptest::prelude::*;
+
+fn weights() -> impl Strategy<Value = Vec<f64>> {
+    prop::collection::vec(
+        prop_oneof![
+            8 => 0.001f64..1000.0,
+            1 => Just(0.0),
+            1 => Just(f64::NAN),
+        ],
+        0..40,
+    )
+}
+
+#[test]
+fn splits_cap_by_weight() {
+    assert_eq!(slots(&[3.0, 1.0], 100), [75, 25]);
+    assert_eq!(slots(&[1.0, 1.0, 1.0], 100), [34, 33, 33]);
+    assert_eq!(slots(&[1.0, 0.0, 1.0], 3), [2, 0, 1]);
+}
+
+#[test]
+fn tiny_weight_still_gets_a_slot() {
+    assert_eq!(slots(&[1000.0, 0.001], 100), [99, 1]);
+}
+
+#[test]
+fn ranges_follow_input_order() {
+    let ranges = allocate(&[("a", 1.0), ("b", 0.0), ("c", 3.0)], 100);
+    let ranges: Vec<_> = ranges
+        .iter()
+        .map(|range| (range.target, range.start, range.end))
+        .collect();
+    assert_eq!(ranges, [("a", 0, 24), ("c", 25, 99)]);
+}
+
+proptest! {
+    #[test]
+    fn assigns_exactly_cap(weights in weights(), cap in 1u32..1000) {
+        let slots = slots(&weights, cap);
+        let total: u32 = slots.iter().sum();
+        if weights.iter().any(|weight| weight.is_finite() && *weight > 0.0) {
+            prop_assert_eq!(total, cap);
+        } else {
+            prop_assert_eq!(total, 0);
+        }
+    }
+
+    #[test]
+    fn every_eligible_weight_gets_a_slot(weights in weights(), cap in 1u32..1000) {
+        let slots = slots(&weights, cap);
+        let eligible = weights.iter().filter(|weight| weight.is_finite() && **weight > 0.0).count();
+        for (weight, slots) in weights.iter().zip(&slots) {
+            if !(weight.is_finite() && *weight > 0.0) {
+                prop_assert_eq!(*slots, 0);
+            } else if eligible <= cap as usize {
+                prop_assert!(*slots >= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn share_stays_close_to_weight(weights in weights(), cap in 100u32..1000) {
+        let slots = slots(&weights, cap);
+        let eligible: Vec<f64> = weights.iter().copied().filter(|weight| weight.is_finite() && *weight > 0.0).collect();
+        let total: f64 = eligible.iter().sum();
+        for (weight, slots) in weights.iter().zip(&slots) {
+            if !(weight.is_finite() && *weight > 0.0) {
+                continue;
+            }
+            // largest remainder meleset paling banyak satu slot, ditambah slot yang
+            // diberikan kepada bobot kecil agar setiap bobot memperoleh minimal satu slot
+            let quota = weight / total * cap as f64;
+            prop_assert!((*slots as f64 - quota).abs() <= 1.0 + eligible.len() as f64);
+        }
+    }
+
+    #[test]
+    fn ranges_are_contiguous_and_complete(weights in weights(), cap in 1u32..1000) {
+        let weights: Vec<(usize, f64)> = weights.into_iter().enumerate().collect();
+        let ranges = allocate(&weights, cap);
+        if ranges.is_empty() {
+            return Ok(());
+        }
+
+        prop_assert_eq!(ranges[0].start, 0);
+        prop_assert_eq!(ranges[ranges.len() - 1].end, cap - 1);
+        for pair in ranges.windows(2) {
+            prop_assert_eq!(pair[0].end + 1, pair[1].start);
+            prop_assert!(pair[0].target < pair[1].target);
+        }
+        for range in &ranges {
+            prop_assert!(range.start <= range.end);
+        }
+    }
+
+    #[test]
+    fn is_deterministic(weights in weights(), cap in 1u32..1000) {
+        prop_assert_eq!(slots(&weights, cap), slots(&weights, cap));
+    }
+}